@@ -79,14 +79,13 @@ impl KatherineFundraising {
                 requested_amount,
                 kickstarter,
                 supporter_id,
-                goal,
             );
         }
 
         let current_withdraw = kickstarter.get_rewards_withdraw(&supporter_id);
         let new_withdraw = current_withdraw + requested_amount;
         kickstarter.rewards_withdraw.insert(&supporter_id, &new_withdraw);
-        self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
     }
 
     /// The contrapart in the withdraw for this function is **remove_from_supported_withdraw**.
@@ -95,7 +94,6 @@ impl KatherineFundraising {
         amount_to_withdraw: Balance,
         kickstarter: &mut Kickstarter,
         supporter_id: &SupporterId,
-        goal: Goal,
     ) {
         let entity = WithdrawEntity::Supporter(supporter_id.to_string());
         let available_stnear = kickstarter.get_after_unfreeze_deposits(&supporter_id)
@@ -104,7 +102,6 @@ impl KatherineFundraising {
             let total_supporter_rewards = self.internal_get_supporter_rewards(
                 &supporter_id,
                 &kickstarter,
-                goal.tokens_to_release_per_stnear,
             );
             if total_supporter_rewards == amount_to_withdraw {
                 let mut supporter = self.internal_get_supporter(&supporter_id);
@@ -166,7 +163,7 @@ impl KatherineFundraising {
             withdraw -= amount;
             kickstarter.rewards_withdraw.insert(&supporter_id, &withdraw);
         }
-        self.kickstarters.replace(kickstarter_id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
 
         // If the claim fails, add the Kickstarter back to the supported projects.
         let mut supporter = self.internal_get_supporter(&supporter_id);
@@ -177,41 +174,214 @@ impl KatherineFundraising {
     }
 }
 
-/****************************************/
-/*  Kickstarter Claim Excedent pTokens  */
-/****************************************/
+/********************/
+/*  Referrer Claim  */
+/********************/
 
 #[near_bindgen]
 impl KatherineFundraising {
-    pub(crate) fn internal_withdraw_excedent(
+    pub(crate) fn internal_claim_referral_rewards(
         &mut self,
         kickstarter: &mut Kickstarter,
-        excedent: Balance
+        referrer_id: AccountId,
     ) {
-        kickstarter.available_reward_tokens -= excedent;
-        self.kickstarters
-            .replace(kickstarter.id as u64, &kickstarter);
+        assert_eq!(
+            kickstarter.successful, Some(true),
+            "Kickstarter was unsuccessful."
+        );
+        assert!(
+            kickstarter.get_winner_goal().cliff_timestamp < get_current_epoch_millis(),
+            "Tokens not released."
+        );
+        let rewards = self.internal_get_available_referral_rewards(&referrer_id, &kickstarter);
+        let amount_tokens = kickstarter.yocto_to_less_decimals(rewards);
+        assert!(amount_tokens > 0, "Referrer does not have available Kickstarter Tokens");
+        let amount_truncated = kickstarter.less_to_24_decimals(amount_tokens);
+
+        let new_withdraw = kickstarter.get_referral_rewards_withdraw(&referrer_id) + amount_truncated;
+        kickstarter.referral_rewards_withdraw.insert(&referrer_id, &new_withdraw);
+        self.internal_save_kickstarter(&kickstarter);
 
-        let excedent_tokens = kickstarter.yocto_to_less_decimals(excedent);
-        let excedent = BalanceJSON::from(excedent);
         nep141_token::ft_transfer(
-            env::predecessor_account_id().clone().try_into().unwrap(),
-            BalanceJSON::from(excedent_tokens),
-            Some("withdraw excedent from kickstarter".to_string()),
+            referrer_id.clone().try_into().unwrap(),
+            amount_tokens.into(),
+            None,
             &kickstarter.token_contract_address,
             1,
             GAS_FOR_FT_TRANSFER,
         ).then(
-            ext_self_kickstarter::kickstarter_withdraw_excedent_callback(
+            ext_self_kickstarter::return_referral_rewards_callback(
+                referrer_id.try_into().unwrap(),
                 kickstarter.id,
-                excedent,
+                amount_truncated.into(),
                 &env::current_account_id(),
                 0,
-                GAS_FOR_FT_TRANSFER,
-            ),
+                GAS_FOR_FT_TRANSFER
+            )
+        );
+    }
+
+    #[private]
+    pub fn return_referral_rewards_callback(
+        &mut self,
+        referrer_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+        amount: U128,
+    ) {
+        let amount = amount.0;
+        let referrer_id = referrer_id.to_string();
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                log!(
+                    "CLAIM: {} pTOKEN of referral rewards transfered to {}",
+                    amount, referrer_id
+                );
+            }
+            PromiseResult::Failed => {
+                log!(
+                    "FAILED: {} pToken not transfered. Recovering {} state.",
+                    amount, referrer_id
+                );
+                let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+                let withdraw = kickstarter.get_referral_rewards_withdraw(&referrer_id);
+                assert!(withdraw >= amount, "Withdraw amount too high.");
+                if withdraw == amount {
+                    kickstarter.referral_rewards_withdraw.remove(&referrer_id);
+                } else {
+                    kickstarter.referral_rewards_withdraw.insert(&referrer_id, &(withdraw - amount));
+                }
+                self.internal_save_kickstarter(&kickstarter);
+            }
+        }
+    }
+}
+
+/****************************/
+/*  Keeper Claim pTokens    */
+/****************************/
+
+#[near_bindgen]
+impl KatherineFundraising {
+    pub(crate) fn internal_claim_keeper_rewards(
+        &mut self,
+        kickstarter: &mut Kickstarter,
+        keeper_id: AccountId,
+    ) {
+        let rewards = kickstarter.get_keeper_rewards(&keeper_id);
+        let amount_tokens = kickstarter.yocto_to_less_decimals(rewards);
+        assert!(amount_tokens > 0, "Keeper does not have available Kickstarter Tokens");
+        let amount_truncated = kickstarter.less_to_24_decimals(amount_tokens);
+
+        if rewards == amount_truncated {
+            kickstarter.keeper_rewards.remove(&keeper_id);
+        } else {
+            kickstarter.keeper_rewards.insert(&keeper_id, &(rewards - amount_truncated));
+        }
+        self.internal_save_kickstarter(&kickstarter);
+
+        nep141_token::ft_transfer(
+            keeper_id.clone().try_into().unwrap(),
+            amount_tokens.into(),
+            None,
+            &kickstarter.token_contract_address,
+            1,
+            GAS_FOR_FT_TRANSFER,
+        ).then(
+            ext_self_kickstarter::return_keeper_rewards_callback(
+                keeper_id.try_into().unwrap(),
+                kickstarter.id,
+                amount_truncated.into(),
+                &env::current_account_id(),
+                0,
+                GAS_FOR_FT_TRANSFER
+            )
         );
     }
 
+    #[private]
+    pub fn return_keeper_rewards_callback(
+        &mut self,
+        keeper_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+        amount: U128,
+    ) {
+        let amount = amount.0;
+        let keeper_id = keeper_id.to_string();
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                log!(
+                    "CLAIM: {} pTOKEN of keeper bounties transfered to {}",
+                    amount, keeper_id
+                );
+            }
+            PromiseResult::Failed => {
+                log!(
+                    "FAILED: {} pToken not transfered. Recovering {} state.",
+                    amount, keeper_id
+                );
+                let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+                let rewards = kickstarter.get_keeper_rewards(&keeper_id);
+                kickstarter.keeper_rewards.insert(&keeper_id, &(rewards + amount));
+                self.internal_save_kickstarter(&kickstarter);
+            }
+        }
+    }
+}
+
+/****************************************/
+/*  Kickstarter Claim Excedent pTokens  */
+/****************************************/
+
+#[near_bindgen]
+impl KatherineFundraising {
+    /// The excedent is refunded to the reward funders, pro rata of their funding.
+    pub(crate) fn internal_withdraw_excedent(
+        &mut self,
+        kickstarter: &mut Kickstarter,
+        excedent: Balance
+    ) {
+        let funders: Vec<(AccountId, Balance)> = kickstarter.reward_funders.to_vec();
+        let total_funded: Balance = funders.iter().map(|(_, funded)| funded).sum();
+        assert!(total_funded > 0, "The Kickstarter has no reward funders!");
+
+        let mut refunds: Vec<(AccountId, Balance)> = Vec::new();
+        let mut total_refunded: Balance = 0;
+        for (funder_id, funded) in funders.into_iter() {
+            // Only whole token units are transferred, the dust is kept as excedent.
+            let share = kickstarter.less_to_24_decimals(
+                kickstarter.yocto_to_less_decimals(proportional(excedent, funded, total_funded))
+            );
+            if share > 0 {
+                total_refunded += share;
+                refunds.push((funder_id, share));
+            }
+        }
+        assert!(total_refunded > 0, "No remaining excedent pTOKEN to withdraw!");
+        kickstarter.available_reward_tokens -= total_refunded;
+        self.internal_save_kickstarter(&kickstarter);
+
+        for (funder_id, share) in refunds.into_iter() {
+            nep141_token::ft_transfer(
+                funder_id.try_into().unwrap(),
+                BalanceJSON::from(kickstarter.yocto_to_less_decimals(share)),
+                Some("withdraw excedent from kickstarter".to_string()),
+                &kickstarter.token_contract_address,
+                1,
+                GAS_FOR_FT_TRANSFER,
+            ).then(
+                ext_self_kickstarter::kickstarter_withdraw_excedent_callback(
+                    kickstarter.id,
+                    BalanceJSON::from(share),
+                    &env::current_account_id(),
+                    0,
+                    GAS_FOR_RESOLVE_TRANSFER,
+                ),
+            );
+        }
+    }
+
     #[private]
     pub fn kickstarter_withdraw_excedent_callback(
         &mut self,
@@ -247,8 +417,7 @@ impl KatherineFundraising {
     ) {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
         kickstarter.available_reward_tokens += amount;
-        self.kickstarters
-            .replace(kickstarter_id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
     }
 }
 
@@ -259,8 +428,8 @@ impl KatherineFundraising {
 #[near_bindgen]
 impl KatherineFundraising {
     pub(crate) fn internal_withdraw_katherine_fee(&mut self, kickstarter: &mut Kickstarter, katherine_fee: Balance) {
-        kickstarter.katherine_fee = Some(0);
-        self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+        kickstarter.katherine_fee_withdraw += katherine_fee;
+        self.internal_save_kickstarter(&kickstarter);
 
         nep141_token::ft_transfer(
             env::predecessor_account_id().clone().try_into().unwrap(),
@@ -303,8 +472,8 @@ impl KatherineFundraising {
                     amount, kickstarter_id
                 );
                 let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
-                kickstarter.katherine_fee = Some(amount);
-                self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+                kickstarter.katherine_fee_withdraw -= amount;
+                self.internal_save_kickstarter(&kickstarter);
             }
         }
     }
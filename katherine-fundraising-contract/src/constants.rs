@@ -10,6 +10,33 @@ pub const ONE_MILLI_NEAR: u128 = NEAR / 1_000;
 pub const BASIS_POINTS: u128 = 10_000;
 pub const NO_DEPOSIT: u128 = 0;
 
+/// Bounds for the contract parameters.
+pub const DEPOSIT_MSG_VERSION: u8 = 1;
+pub const MAX_KATHERINE_FEE_PERCENT: u32 = 2_000;
+pub const DEFAULT_MAX_GOALS_PER_KICKSTARTER: u8 = 5;
+pub const MAX_GOALS_PER_KICKSTARTER: u8 = 10;
+pub const MAX_MIN_DEPOSIT_AMOUNT: u128 = 100 * NEAR;
+pub const MAX_REFERRAL_FEE_PERCENT: u32 = 1_000;
+pub const MAX_DEPOSIT_TIERS: usize = 5;
+pub const MAX_DEPOSIT_TIER_MULTIPLIER: u32 = 20_000;
+/// Max steps of a vesting schedule, or points of a custom curve.
+pub const MAX_VESTING_STEPS: u64 = 60;
+/// The excedent is refunded to all the funders in a single call, the owner is not counted.
+pub const MAX_REWARD_FUNDERS: u64 = 4;
+
+/// Size limits for the Kickstarter metadata.
+pub const MAX_METADATA_DESCRIPTION_LENGTH: usize = 1_000;
+pub const MAX_METADATA_URL_LENGTH: usize = 256;
+pub const MAX_METADATA_SOCIAL_LINKS: usize = 5;
+
+/// Storage of a Supporter and of each supported project, measured with 64 chars account ids
+/// plus a margin. A project includes the deposit, the withdraws, and the referral, tier and
+/// time-weighted entries of the Supporter.
+pub const SUPPORTER_STORAGE_BYTES: u64 = 1_000;
+pub const SUPPORTED_PROJECT_STORAGE_BYTES: u64 = 3_500;
+/// Storage of the audit entry of the deposits made for another account.
+pub const DEPOSIT_ON_BEHALF_STORAGE_BYTES: u64 = 600;
+
 /// Amount of gas for fungible token transfers.
 pub const TGAS: Gas = 1_000_000_000_000;
 pub const FIVE_TGAS: Gas = 5 * TGAS;
@@ -17,6 +44,10 @@ pub const GAS_FOR_FT_TRANSFER: Gas = 47 * TGAS;
 pub const GAS_FOR_RESOLVE_TRANSFER: Gas = 11 * TGAS;
 pub const GAS_FOR_GET_STNEAR : Gas = 10 * TGAS;
 pub const GAS_FOR_INTEREST_WITHDRAW : Gas = 200 * TGAS;
+pub const GAS_FOR_DEPOSIT_AND_STAKE : Gas = 50 * TGAS;
+pub const GAS_FOR_DEPOSIT_NEAR_CALLBACK : Gas = 80 * TGAS;
+// Gas for each Kickstarter in the process and unfreeze batch callbacks.
+pub const GAS_FOR_BATCH_ITEM : Gas = 5 * TGAS;
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum Keys {
@@ -29,6 +60,22 @@ pub enum Keys {
     Deposits,
     RewardWithdraws,
     StnearWithdraws,
+    Operators,
+    FeeCollectors,
+    Robots,
+    KickstarterMetadata,
+    Referrers,
+    ReferredDeposits,
+    ReferralWithdraws,
+    DepositsOnBehalf,
+    StorageDeposits,
+    TierDeposits,
+    RewardFunders,
+    FundersAllowlist,
+    KeeperRewards,
+    TimeWeightedDeposits,
+    SupporterReferredDeposits,
+    DepositAmounts,
 }
 
 impl Keys {
@@ -44,6 +91,22 @@ impl Keys {
 			Keys::Deposits => format!("{}{}", "D", id),
 			Keys::RewardWithdraws => format!("{}{}", "RW", id),
 			Keys::StnearWithdraws => format!("{}{}", "SW", id),
+			Keys::Operators => format!("{}{}", "Op", id),
+			Keys::FeeCollectors => format!("{}{}", "Fc", id),
+			Keys::Robots => format!("{}{}", "Rb", id),
+			Keys::KickstarterMetadata => format!("{}{}", "Km", id),
+			Keys::Referrers => format!("{}{}", "Rf", id),
+			Keys::ReferredDeposits => format!("{}{}", "Rd", id),
+			Keys::ReferralWithdraws => format!("{}{}", "RfW", id),
+			Keys::DepositsOnBehalf => format!("{}{}", "DoB", id),
+			Keys::StorageDeposits => format!("{}{}", "Sd", id),
+			Keys::TierDeposits => format!("{}{}", "Td", id),
+			Keys::RewardFunders => format!("{}{}", "Rfu", id),
+			Keys::FundersAllowlist => format!("{}{}", "Fal", id),
+			Keys::KeeperRewards => format!("{}{}", "Kr", id),
+			Keys::TimeWeightedDeposits => format!("{}{}", "Twd", id),
+			Keys::SupporterReferredDeposits => format!("{}{}", "Srd", id),
+			Keys::DepositAmounts => format!("{}{}", "Da", id),
 		}
     }
 }
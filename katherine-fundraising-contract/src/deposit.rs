@@ -1,7 +1,8 @@
 use crate::*;
 use near_sdk::json_types::{ValidAccountId, U128};
-use near_sdk::{env, log, near_bindgen, PromiseOrValue};
+use near_sdk::{env, log, near_bindgen, Promise, PromiseOrValue};
 
+use crate::interface::*;
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 
 #[near_bindgen]
@@ -12,176 +13,482 @@ impl FungibleTokenReceiver for KatherineFundraising {
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        let kickstarter_id = match msg.parse::<KickstarterId>() {
-            Ok(_id) => _id,
-            Err(_) => panic!("Invalid KickstarterId."),
-        };
+        let deposit_msg = parse_deposit_msg(&msg);
+        let kickstarter_id = deposit_msg.kickstarter_id;
         let mut kickstarter: Kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_deposits_not_paused(&kickstarter);
         let amount = amount.0;
-        if env::predecessor_account_id() == self.metapool_contract_address {
+        let is_st_near = env::predecessor_account_id() == self.metapool_contract_address;
+        let action = deposit_msg.action.unwrap_or(
+            if is_st_near { DepositAction::Deposit } else { DepositAction::RewardTokens }
+        );
+        match action {
+            DepositAction::Deposit => assert!(is_st_near, "Supporters can only deposit stNEAR."),
+            DepositAction::RewardTokens => assert!(!is_st_near, "stNEAR is not a reward token."),
+        }
+        let accepted = if action == DepositAction::Deposit {
             // Deposit is in stNEAR.
-            self.assert_min_deposit_amount(amount);
+            let supporter_id: SupporterId = match deposit_msg.beneficiary {
+                Some(beneficiary_id) => beneficiary_id.into(),
+                None => sender_id.to_string(),
+            };
+            let accepted = self.process_supporter_deposit(
+                &supporter_id,
+                sender_id.as_ref(),
+                &amount,
+                deposit_msg.referrer.map(|referrer_id| referrer_id.into()),
+                &mut kickstarter
+            );
             log!(
-                "DEPOSIT: {} stNEAR deposited from {} to KickstarterId {}",
-                amount,
+                "DEPOSIT: {} stNEAR deposited from {} for {} to KickstarterId {}",
+                accepted,
                 sender_id.as_ref(),
-                msg
+                supporter_id,
+                kickstarter_id
             );
-            self.process_supporter_deposit(sender_id.as_ref(), &amount, &mut kickstarter);
+            accepted
         } else {
             // Deposit is in a Kickstarter Token.
+            assert!(
+                deposit_msg.referrer.is_none() && deposit_msg.beneficiary.is_none(),
+                "A referrer or a beneficiary is only allowed for the Supporters deposits."
+            );
+            let accepted = self.process_kickstarter_deposit(sender_id.as_ref(), amount, &mut kickstarter);
             log!(
                 "DEPOSIT: {} pTOKEN deposited from {} to KickstarterId {}",
-                amount,
+                accepted,
                 sender_id.as_ref(),
-                msg
+                kickstarter_id
             );
-            self.process_kickstarter_deposit(amount, &mut kickstarter);
-        }
+            accepted
+        };
         // Return unused amount
-        PromiseOrValue::Value(U128::from(0))
+        if accepted < amount {
+            log!("REFUND: {} returned to {}", amount - accepted, sender_id.as_ref());
+        }
+        PromiseOrValue::Value(U128::from(amount - accepted))
     }
 }
 
+/// Parses the `msg` of an `ft_transfer_call`, as a bare KickstarterId or as a `DepositMsg` JSON.
+pub(crate) fn parse_deposit_msg(msg: &str) -> DepositMsg {
+    if let Ok(kickstarter_id) = msg.trim().parse::<KickstarterId>() {
+        return DepositMsg {
+            version: None,
+            kickstarter_id,
+            action: None,
+            referrer: None,
+            beneficiary: None,
+        };
+    }
+    let deposit_msg: DepositMsg = match near_sdk::serde_json::from_str(msg) {
+        Ok(deposit_msg) => deposit_msg,
+        Err(err) => panic!("Invalid KickstarterId or deposit msg: {}", err),
+    };
+    let version = deposit_msg.version.unwrap_or(DEPOSIT_MSG_VERSION);
+    assert!(
+        version == DEPOSIT_MSG_VERSION,
+        "Unsupported deposit msg version {}",
+        version
+    );
+    deposit_msg
+}
+
+/*******************************/
+/*  NEAR deposits to Meta Pool  */
+/*******************************/
+
 #[near_bindgen]
 impl KatherineFundraising {
-    fn assert_min_deposit_amount(&self, amount: Balance) {
+    /// Stakes the attached NEAR in Meta Pool, and deposits the minted stNEAR in the Kickstarter
+    /// for the beneficiary, the predecessor by default. If the staking fails, or if the stNEAR
+    /// cannot be deposited, the funds are returned to the predecessor.
+    #[payable]
+    pub fn deposit_near(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        referrer_id: Option<ValidAccountId>,
+        beneficiary_id: Option<ValidAccountId>,
+    ) -> Promise {
+        let min_prepaid_gas = GAS_FOR_DEPOSIT_AND_STAKE + GAS_FOR_DEPOSIT_NEAR_CALLBACK + FIVE_TGAS;
+        assert!(
+            env::prepaid_gas() > min_prepaid_gas,
+            "gas required {}",
+            min_prepaid_gas
+        );
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "Attach the NEAR to deposit!");
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_deposits_not_paused(&kickstarter);
+        kickstarter.assert_not_cancelled();
+        kickstarter.assert_within_funding_period();
+        kickstarter.assert_enough_reward_tokens();
+
+        // The missing storage of the Supporter and of the depositor is charged from the attached NEAR.
+        let depositor_id = env::predecessor_account_id();
+        let supporter_id: SupporterId = match &beneficiary_id {
+            Some(beneficiary_id) => beneficiary_id.to_string(),
+            None => depositor_id.clone(),
+        };
+        let storage_charge = self.internal_missing_storage(&supporter_id, &kickstarter);
+        let depositor_storage_charge =
+            self.internal_missing_storage_on_behalf(&depositor_id, &supporter_id, &kickstarter);
+        let total_storage_charge = storage_charge + depositor_storage_charge;
+        assert!(
+            amount > total_storage_charge,
+            "The attached NEAR must cover the storage of {}",
+            total_storage_charge
+        );
+        let amount = amount - total_storage_charge;
         assert!(
             amount >= self.min_deposit_amount,
-            "minimum deposit amount is {}",
-            self.min_deposit_amount
+            "minimum deposit amount is {} after the storage of {}",
+            self.min_deposit_amount,
+            total_storage_charge
         );
+        if storage_charge > 0 {
+            self.internal_storage_deposit(&supporter_id, storage_charge);
+        }
+        if depositor_storage_charge > 0 {
+            self.internal_storage_deposit(&depositor_id, depositor_storage_charge);
+        }
+
+        let depositor_id: ValidAccountId = depositor_id.try_into().unwrap();
+        ext_self_metapool::deposit_and_stake(
+            &self.metapool_contract_address,
+            amount,
+            GAS_FOR_DEPOSIT_AND_STAKE,
+        ).then(
+            ext_self_metapool::deposit_near_callback(
+                depositor_id,
+                kickstarter_id,
+                referrer_id,
+                beneficiary_id,
+                BalanceJSON::from(amount),
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_DEPOSIT_NEAR_CALLBACK,
+            )
+        )
+    }
+
+    /// Returns the stNEAR deposited in the Kickstarter.
+    #[private]
+    pub fn deposit_near_callback(
+        &mut self,
+        depositor_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+        referrer_id: Option<ValidAccountId>,
+        beneficiary_id: Option<ValidAccountId>,
+        amount: BalanceJSON,
+    ) -> BalanceJSON {
+        let amount = amount.0;
+        let st_near_amount = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(result) => {
+                near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0
+            },
+            PromiseResult::Failed => {
+                log!(
+                    "FAILED: {} NEAR not staked in Meta Pool. Returning to {}.",
+                    amount, depositor_id.as_ref()
+                );
+                Promise::new(depositor_id.as_ref().clone()).transfer(amount);
+                return BalanceJSON::from(0);
+            },
+        };
+
+        // The deposit conditions could have changed while staking.
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        let supporter_id: SupporterId = match beneficiary_id {
+            Some(beneficiary_id) => beneficiary_id.into(),
+            None => depositor_id.to_string(),
+        };
+        let accepted = match self.check_supporter_deposit(&supporter_id, depositor_id.as_ref(), st_near_amount, &kickstarter) {
+            Ok(_) => {
+                let accepted = self.process_supporter_deposit(
+                    &supporter_id,
+                    depositor_id.as_ref(),
+                    &st_near_amount,
+                    referrer_id.map(|referrer_id| referrer_id.into()),
+                    &mut kickstarter
+                );
+                log!(
+                    "DEPOSIT: {} NEAR staked as {} stNEAR, {} stNEAR deposited from {} for {} to KickstarterId {}",
+                    amount,
+                    st_near_amount,
+                    accepted,
+                    depositor_id.as_ref(),
+                    supporter_id,
+                    kickstarter_id
+                );
+                accepted
+            },
+            Err(reason) => {
+                log!("FAILED: {}.", reason);
+                0
+            },
+        };
+        if accepted < st_near_amount {
+            let unused = st_near_amount - accepted;
+            log!("REFUND: {} stNEAR returned to {}", unused, depositor_id.as_ref());
+            nep141_token::ft_transfer(
+                depositor_id,
+                BalanceJSON::from(unused),
+                None,
+                &self.metapool_contract_address,
+                1,
+                GAS_FOR_FT_TRANSFER,
+            );
+        }
+        BalanceJSON::from(accepted)
+    }
+}
+
+/***************************/
+/*  Deposits to Katherine  */
+/***************************/
+
+#[near_bindgen]
+impl KatherineFundraising {
+    /// Returns the part of a stNEAR deposit accepted by the Kickstarter, up to the hard cap,
+    /// or the reason why it cannot be accepted.
+    pub(crate) fn check_supporter_deposit(
+        &self,
+        supporter_id: &AccountId,
+        depositor_id: &AccountId,
+        amount: Balance,
+        kickstarter: &Kickstarter,
+    ) -> Result<Balance, String> {
+        if self.deposits_paused || kickstarter.deposits_paused {
+            return Err("Deposits are paused!".to_string());
+        }
+        if kickstarter.is_cancelled() {
+            return Err("Kickstarter was cancelled!".to_string());
+        }
+        if !kickstarter.is_within_funding_period() {
+            return Err("Not within the funding period.".to_string());
+        }
+        if !kickstarter.enough_reward_tokens {
+            return Err(
+                "Supporters cannot deposit until the Kickstarter covers the required rewards!".to_string()
+            );
+        }
+        if amount < self.min_deposit_amount {
+            return Err(format!("minimum deposit amount is {}", self.min_deposit_amount));
+        }
+        if kickstarter.total_deposited >= kickstarter.deposits_hard_cap {
+            return Err("The deposits hard cap was reached!".to_string());
+        }
+        let missing_storage = self.internal_missing_storage(&supporter_id, &kickstarter);
+        if missing_storage > 0 {
+            return Err(format!(
+                "{} must cover the storage with storage_deposit, {} missing",
+                supporter_id, missing_storage
+            ));
+        }
+        let missing_storage = self.internal_missing_storage_on_behalf(&depositor_id, &supporter_id, &kickstarter);
+        if missing_storage > 0 {
+            return Err(format!(
+                "{} must cover the storage of the deposit on behalf with storage_deposit, {} missing",
+                depositor_id, missing_storage
+            ));
+        }
+        Ok(std::cmp::min(amount, kickstarter.deposits_hard_cap - kickstarter.total_deposited))
     }
 
-    /// Process a stNEAR deposit to Katherine Contract.
-    fn process_supporter_deposit(
+    /// Process a stNEAR deposit to Katherine Contract. Returns the accepted amount.
+    /// The deposit belongs to the Supporter, the depositor is only recorded if it is another account.
+    pub(crate) fn process_supporter_deposit(
         &mut self,
         supporter_id: &AccountId,
+        depositor_id: &AccountId,
         amount: &Balance,
+        referrer_id: Option<AccountId>,
         kickstarter: &mut Kickstarter,
-    ) {
-        // Update Kickstarter
-        kickstarter.assert_within_funding_period();
-        kickstarter.assert_enough_reward_tokens();
+    ) -> Balance {
+        let accepted = match self.check_supporter_deposit(&supporter_id, &depositor_id, *amount, kickstarter) {
+            Ok(accepted) => accepted,
+            Err(reason) => panic!("{}", reason),
+        };
 
-        let new_total_deposited = kickstarter.total_deposited + amount;
-        assert!(
-            new_total_deposited <= kickstarter.deposits_hard_cap,
-            "The deposits hard cap cannot be exceeded!"
-        );
-        kickstarter.total_deposited = new_total_deposited;
-        kickstarter.update_supporter_deposits(&supporter_id, amount);
-        self.kickstarters
-            .replace(kickstarter.id as u64, &kickstarter);
+        // Update Kickstarter
+        kickstarter.total_deposited += accepted;
+        kickstarter.update_supporter_deposits(&supporter_id, &accepted);
+        kickstarter.update_referred_deposits(&supporter_id, referrer_id, accepted);
+        if depositor_id != supporter_id {
+            if kickstarter.deposits_on_behalf.get(&(depositor_id.clone(), supporter_id.clone())).is_none() {
+                self.internal_storage_charge_on_behalf(&depositor_id);
+            }
+            kickstarter.record_deposit_on_behalf(&depositor_id, &supporter_id, accepted);
+        }
+        self.internal_save_kickstarter(&kickstarter);
 
         // Update Supporter.
         let mut supporter = self.internal_get_supporter(&supporter_id);
         supporter.supported_projects.insert(&kickstarter.id);
         self.supporters.insert(&supporter_id, &supporter);
+        accepted
     }
 
-    /// Process a reward token deposit to Katherine Contract.
+    /// Process a reward token deposit to Katherine Contract. Only the tokens required to cover
+    /// the max rewards and the Katherine fee are accepted, the amount is in the token decimals.
     fn process_kickstarter_deposit(
         &mut self,
+        funder_id: &AccountId,
         amount: Balance,
         kickstarter: &mut Kickstarter,
-    ) {
+    ) -> Balance {
         assert_eq!(
             &env::predecessor_account_id(),
             &kickstarter.token_contract_address,
             "Deposited tokens do not correspond to the Kickstarter contract."
         );
+        kickstarter.assert_not_cancelled();
         assert!(
             get_current_epoch_millis() < kickstarter.close_timestamp,
             "Kickstarter Tokens should be provided before the funding period ends."
         );
-        let amount = kickstarter.less_to_24_decimals(amount);
-        let max_tokens_to_release = self.calculate_max_tokens_to_release(&kickstarter);
-        let min_tokens_to_allow_support = max_tokens_to_release
-            + self.calculate_katherine_fee(max_tokens_to_release);
-        kickstarter.available_reward_tokens += amount;
+        assert!(
+            kickstarter.is_allowed_reward_funder(funder_id),
+            "{} is not allowed to fund the Kickstarter rewards",
+            funder_id
+        );
+        let min_tokens_to_allow_support = self.calculate_min_tokens_to_allow_support(&kickstarter);
+        assert!(
+            kickstarter.available_reward_tokens < min_tokens_to_allow_support,
+            "The Kickstarter already covers the required rewards!"
+        );
+        // Round up, the missing tokens could have more decimals than the token.
+        let unit = kickstarter.less_to_24_decimals(1);
+        let missing = kickstarter.yocto_to_less_decimals(
+            min_tokens_to_allow_support - kickstarter.available_reward_tokens + unit - 1
+        );
+        let accepted = std::cmp::min(amount, missing);
+        kickstarter.available_reward_tokens += kickstarter.less_to_24_decimals(accepted);
+        kickstarter.update_reward_funders(funder_id, kickstarter.less_to_24_decimals(accepted));
         kickstarter.enough_reward_tokens = {
             kickstarter.available_reward_tokens >= min_tokens_to_allow_support
         };
-        self.kickstarters
-            .replace(kickstarter.id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
+        accepted
     }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
-#[allow(unused_imports)]
 mod tests {
-    use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
     use near_contract_standards::storage_management::StorageManagement;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::{testing_env, Balance};
-    use near_sdk::{MockedBlockchain, ValidatorId};
     use std::convert::TryInto;
 
     use super::*;
-
-    fn get_time_millis(ctx: &VMContextBuilder) -> u64 {
-        ctx.context.block_timestamp / 1_000_000
-    }
-
-    fn acc_metapool() -> ValidAccountId {
-        "metapool".try_into().unwrap()
-    }
-
-    fn acc_owner() -> ValidAccountId {
-        "owner".try_into().unwrap()
-    }
-
-    const STARTING_TIMESTAMP: u64 = 100_000_000_000_000_000;
-
-    fn setup_contract(predecessor: ValidAccountId) -> (VMContextBuilder, KatherineFundraising) {
-        let mut context = VMContextBuilder::new();
-        testing_env!(context.build());
-        testing_env!(context
-            .predecessor_account_id(predecessor)
-            .block_timestamp(STARTING_TIMESTAMP)
-            .build());
-        let contract = KatherineFundraising::new(
-            acc_owner().to_string(), // owner
-            0,
-            acc_metapool().to_string(),
-            1,
-        );
-        (context, contract)
-    }
+    use crate::tests::unit_test_utils::*;
 
     #[test]
     fn add_supporter_with_ext_callback() {
-        let supporter = accounts(0);
-        let kickstarter_owner = accounts(1);
-        let kickstarter_token_acc = accounts(2);
-        let (mut ctx, mut ctr) = setup_contract(acc_owner());
-
-        // create a kickstarter
-        let kickstarter_id = ctr.create_kickstarter(
-            "first_kickstarter".to_owned(),
-            "FK".to_owned(),
-            kickstarter_owner.to_string(),
-            get_time_millis(&ctx),
-            get_time_millis(&ctx) + 1_000 * 60 * 5,
-            kickstarter_token_acc.to_string(),
-        );
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+
         // become a supporter
-        testing_env!(ctx.predecessor_account_id(acc_metapool()).build());
-        let promise = ctr.ft_on_transfer(supporter.clone(), 1.into(), kickstarter_id.to_string());
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        let missing_storage = contract.storage_cost(1);
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, missing_storage);
+        contract.storage_deposit(None, None);
+        set_attached_deposit(&mut context, 0);
+        set_predecessor(&mut context, METAPOOL_CONTRACT_ADDRESS);
+        let promise = contract.ft_on_transfer(
+            SUPPORTER_ACCOUNT.try_into().unwrap(),
+            U128::from(ntoy(10)),
+            kickstarter_id.to_string()
+        );
 
-        match promise {
-            PromiseOrValue::Promise(_) => {
-                println!("error, method returned a promise");
-                std::panic!();
-            }
+        let refund = match promise {
+            PromiseOrValue::Promise(_) => panic!("error, method returned a promise"),
             PromiseOrValue::Value(v) => v,
         };
+        assert_eq!(refund.0, 0);
 
-        let kickstarter_data = ctr.get_kickstarter(kickstarter_id);
+        let kickstarter_data = contract.get_kickstarter(kickstarter_id);
         assert_eq!(
             kickstarter_data.total_supporters, 1,
             "incorrrect number of supporters for kickstarter"
         );
     }
+
+    #[test]
+    fn deposit_near_after_the_storage() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        let storage_charge = contract.internal_missing_storage(&SUPPORTER_ACCOUNT.to_string(), &kickstarter);
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, storage_charge + MIN_DEPOSIT_AMOUNT);
+        contract.deposit_near(kickstarter_id, None, None);
+        assert_eq!(contract.internal_storage_deposit_of(&SUPPORTER_ACCOUNT.to_string()), storage_charge);
+    }
+
+    #[test]
+    #[should_panic(expected = "minimum deposit amount is 2 after the storage")]
+    fn deposit_near_below_the_min_deposit_after_the_storage() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        let storage_charge = contract.internal_missing_storage(&SUPPORTER_ACCOUNT.to_string(), &kickstarter);
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, storage_charge + MIN_DEPOSIT_AMOUNT - 1);
+        contract.deposit_near(kickstarter_id, None, None);
+    }
+
+    #[test]
+    fn parse_deposit_msg_formats() {
+        let expected = DepositMsg {
+            version: None,
+            kickstarter_id: 3,
+            action: None,
+            referrer: None,
+            beneficiary: None,
+        };
+        assert_eq!(parse_deposit_msg("3"), expected);
+        assert_eq!(parse_deposit_msg(r#"{"kickstarter_id":3}"#), expected);
+        assert_eq!(
+            parse_deposit_msg(r#"{"version":1,"kickstarter_id":3,"action":"reward_tokens"}"#),
+            DepositMsg {
+                version: Some(1),
+                kickstarter_id: 3,
+                action: Some(DepositAction::RewardTokens),
+                referrer: None,
+                beneficiary: None,
+            }
+        );
+        assert_eq!(
+            parse_deposit_msg(r#"{"kickstarter_id":3,"referrer":"bob.near"}"#).referrer,
+            Some("bob.near".try_into().unwrap())
+        );
+        assert_eq!(
+            parse_deposit_msg(r#"{"kickstarter_id":3,"beneficiary":"alice.near"}"#).beneficiary,
+            Some("alice.near".try_into().unwrap())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid KickstarterId or deposit msg")]
+    fn parse_deposit_msg_unknown_field() {
+        parse_deposit_msg(r#"{"kickstarter_id":3,"gift":true}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported deposit msg version 2")]
+    fn parse_deposit_msg_unknown_version() {
+        parse_deposit_msg(r#"{"version":2,"kickstarter_id":3}"#);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::*;
+use crate::validation::KickstarterConfig;
 use near_sdk::serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
@@ -16,6 +17,12 @@ pub struct Goal {
     pub cliff_timestamp: EpochMillis,
     /// Date to finish the delivery of the Kickstarter Tokens
     pub end_timestamp: EpochMillis,
+    /// Min number of Supporters to get this Goal, 0 for no condition
+    pub min_supporters: u32,
+    /// Max share of the total deposited held by a single Supporter, in basis points
+    pub max_supporter_share: Option<BasisPoints>,
+    /// Release of the Kickstarter Tokens between the cliff and the end
+    pub vesting_schedule: VestingSchedule,
 }
 
 impl Goal {
@@ -28,10 +35,47 @@ impl Goal {
             tokens_to_release_per_stnear: BalanceJSON::from(self.tokens_to_release_per_stnear),
             cliff_timestamp: self.cliff_timestamp,
             end_timestamp: self.end_timestamp,
+            min_supporters: self.min_supporters,
+            max_supporter_share: self.max_supporter_share,
+            vesting_schedule: self.vesting_schedule.clone(),
+        }
+    }
+
+    /// Kickstarter Tokens released from the amount at the current timestamp.
+    pub fn get_released(&self, amount: Balance) -> Balance {
+        self.vesting_schedule.get_released(
+            amount,
+            self.cliff_timestamp,
+            self.end_timestamp,
+            get_current_epoch_millis(),
+        )
+    }
+
+    pub(crate) fn from_input(id: GoalId, input: GoalInput) -> Self {
+        Goal {
+            id,
+            name: input.name,
+            desired_amount: Balance::from(input.desired_amount),
+            unfreeze_timestamp: input.unfreeze_timestamp,
+            tokens_to_release_per_stnear: Balance::from(input.tokens_to_release_per_stnear),
+            cliff_timestamp: input.cliff_timestamp,
+            end_timestamp: input.end_timestamp,
+            min_supporters: input.min_supporters,
+            max_supporter_share: input.max_supporter_share,
+            vesting_schedule: input.vesting_schedule,
         }
     }
 }
 
+impl Kickstarter {
+    /// Goals could only be changed before the funding period, when there are no deposits.
+    pub(crate) fn assert_goals_can_be_changed(&self) {
+        self.assert_goal_status();
+        self.assert_not_cancelled();
+        self.assert_before_funding_period();
+    }
+}
+
 #[near_bindgen]
 impl KatherineFundraising {
     pub(crate) fn internal_create_goal(
@@ -43,57 +87,81 @@ impl KatherineFundraising {
         tokens_to_release_per_stnear: BalanceJSON,
         cliff_timestamp: EpochMillis,
         end_timestamp: EpochMillis,
+        min_supporters: Option<u32>,
+        max_supporter_share: Option<BasisPoints>,
+        vesting_schedule: Option<VestingSchedule>,
     ) -> GoalId {
-        kickstarter.assert_goal_status();
-        kickstarter.assert_before_funding_period();
-        kickstarter.assert_number_of_goals(self.max_goals_per_kickstarter);
+        kickstarter.assert_goals_can_be_changed();
 
-        let desired_amount = Balance::from(desired_amount);
-        let tokens_to_release_per_stnear = Balance::from(tokens_to_release_per_stnear);
         let id = kickstarter.get_number_of_goals();
-        assert!(
-            kickstarter.deposits_hard_cap >= desired_amount,
-            "Desired amount must not exceed the deposits hard cap!"
-        );
-        assert!(
-            kickstarter.max_tokens_to_release_per_stnear >= tokens_to_release_per_stnear,
-            "Tokens to release must not exceed the max tokens to release per stNEAR!"
+        let goal = Goal::from_input(
+            id,
+            GoalInput {
+                name,
+                desired_amount,
+                unfreeze_timestamp,
+                tokens_to_release_per_stnear,
+                cliff_timestamp,
+                end_timestamp,
+                min_supporters: min_supporters.unwrap_or(0),
+                max_supporter_share,
+                vesting_schedule: vesting_schedule.unwrap_or_default(),
+            },
         );
-        if id > 0 {
-            let last_goal = kickstarter.goals.get((id - 1) as u64).unwrap();
-            assert!(
-                desired_amount >= last_goal.desired_amount,
-                "Next goal cannot have a lower desired amount that the last goal!"
-            );
-            assert!(
-                unfreeze_timestamp <= last_goal.unfreeze_timestamp,
-                "Next goal cannot freeze supporter funds any longer than the last goal!"
-            );
-            assert!(
-                tokens_to_release_per_stnear >= last_goal.tokens_to_release_per_stnear,
-                "Next goal cannot release less pTOKEN than the last goal!"
-            );
+        let mut goals: Vec<Goal> = kickstarter.goals.iter().collect();
+        goals.push(goal);
+        let config = KickstarterConfig {
+            goals,
+            ..kickstarter.to_config()
+        };
+        self.assert_valid_config(&config);
+        kickstarter.goals.push(config.goals.last().unwrap());
+        self.internal_save_kickstarter(&kickstarter);
+        id
+    }
+
+    /// Replaces all the goals at once, the ids are reassigned in order.
+    pub(crate) fn internal_set_goals(&mut self, kickstarter: &mut Kickstarter, goals: Vec<GoalInput>) {
+        kickstarter.assert_goals_can_be_changed();
+        let goals: Vec<Goal> = goals
+            .into_iter()
+            .enumerate()
+            .map(|(id, input)| Goal::from_input(id as GoalId, input))
+            .collect();
+        let config = KickstarterConfig {
+            goals,
+            ..kickstarter.to_config()
+        };
+        self.assert_valid_config(&config);
+        kickstarter.goals.clear();
+        for goal in config.goals.iter() {
+            kickstarter.goals.push(goal);
         }
-        let goal = Goal {
-            id,
-            name,
-            desired_amount,
-            unfreeze_timestamp,
-            tokens_to_release_per_stnear,
-            cliff_timestamp,
-            end_timestamp,
+        self.internal_save_kickstarter(&kickstarter);
+    }
+
+    pub(crate) fn internal_update_goal(
+        &mut self,
+        kickstarter: &mut Kickstarter,
+        goal_id: GoalId,
+        goal: GoalInput,
+    ) {
+        kickstarter.assert_goals_can_be_changed();
+        let mut goals: Vec<Goal> = kickstarter.goals.iter().collect();
+        assert!((goal_id as usize) < goals.len(), "Goal not found!");
+        goals[goal_id as usize] = Goal::from_input(goal_id, goal);
+        let config = KickstarterConfig {
+            goals,
+            ..kickstarter.to_config()
         };
-        kickstarter.goals.push(&goal);
-        self.kickstarters
-            .replace(kickstarter.id as u64, &kickstarter);
-        goal.id
+        self.assert_valid_config(&config);
+        kickstarter.goals.replace(goal_id as u64, &config.goals[goal_id as usize]);
+        self.internal_save_kickstarter(&kickstarter);
     }
 
     pub(crate) fn internal_delete_last_goal(&mut self, kickstarter: &mut Kickstarter) {
-        kickstarter.assert_goal_status();
-        kickstarter.assert_before_funding_period();
+        kickstarter.assert_goals_can_be_changed();
         kickstarter.goals.pop();
-        self.kickstarters
-            .replace(kickstarter.id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
     }
 }
@@ -36,9 +36,11 @@ impl KatherineFundraising {
         let interest = kickstarter.calculate_interest(price_at_freeze, price_at_unfreeze, current_withdraw);
 
         if interest > 0 {
+            let had_pending_interest = kickstarter.has_pending_interest();
             let new_withdraw = current_withdraw + interest;
             kickstarter.stnear_withdraw.insert(&entity, &new_withdraw);
-            self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+            self.internal_update_pending_interest(had_pending_interest, &kickstarter);
+            self.internal_save_kickstarter(&kickstarter);
 
             nep141_token::ft_transfer(
                 receiver_id.clone().try_into().unwrap(),
@@ -98,9 +100,11 @@ impl KatherineFundraising {
         let current_withdraw = kickstarter.get_stnear_withdraw(&entity);
         assert!(current_withdraw >= amount, "Withdrawn amount is higher than expected");
 
+        let had_pending_interest = kickstarter.has_pending_interest();
         let new_withdraw = current_withdraw - amount;
         kickstarter.stnear_withdraw.insert(&entity, &new_withdraw);
-        self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+        self.internal_update_pending_interest(had_pending_interest, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
     }
 
     pub(crate) fn kickstarter_withdraw_before_unfreeze(
@@ -157,6 +161,17 @@ impl KatherineFundraising {
 }
 
 impl Kickstarter {
+    /// After the unfreeze, the interest not withdrawn by the Kickstarter is still held in stNEAR.
+    pub(crate) fn has_pending_interest(&self) -> bool {
+        match (self.stnear_price_at_freeze, self.stnear_price_at_unfreeze) {
+            (Some(price_at_freeze), Some(price_at_unfreeze)) if price_at_unfreeze > price_at_freeze => {
+                let current_withdraw = self.get_stnear_withdraw(&WithdrawEntity::Kickstarter);
+                self.calculate_interest(price_at_freeze, price_at_unfreeze, current_withdraw) > 0
+            }
+            _ => false,
+        }
+    }
+
     /// Function (5) from the Katherine math.
     fn calculate_interest(
         &self,
@@ -28,6 +28,7 @@ pub trait ExtSelfMetapool {
         supporter_id: ValidAccountId,
         kickstarter_id: KickstarterIdJSON,
         amount: U128,
+        withdrawn: Option<WithdrawnDepositsJSON>,
     );
 
     fn return_tokens_after_unfreeze_callback(
@@ -38,6 +39,17 @@ pub trait ExtSelfMetapool {
     );
 
     fn get_st_near_price(&self) -> U128String;
+
+    fn deposit_and_stake(&mut self) -> U128;
+
+    fn deposit_near_callback(
+        &mut self,
+        depositor_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+        referrer_id: Option<ValidAccountId>,
+        beneficiary_id: Option<ValidAccountId>,
+        amount: U128,
+    ) -> U128;
 }
 
 #[ext_contract(ext_self_kickstarter)]
@@ -46,6 +58,7 @@ pub trait ExtSelfKickstarter {
         &mut self,
         kickstarter_id: KickstarterIdJSON,
         goal_id: GoalIdJSON,
+        keeper_id: ValidAccountId,
     );
 
     fn kickstarter_withdraw_excedent_callback(
@@ -76,7 +89,36 @@ pub trait ExtSelfKickstarter {
 
     fn set_stnear_price_at_unfreeze(
         &mut self,
-        kickstarter_id: KickstarterIdJSON
+        kickstarter_id: KickstarterIdJSON,
+        keeper_id: ValidAccountId,
+    );
+
+    fn process_kickstarters_callback(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+        results: Vec<BatchResultJSON>,
+        keeper_id: ValidAccountId,
+    ) -> Vec<BatchResultJSON>;
+
+    fn unfreeze_kickstarters_callback(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+        results: Vec<BatchResultJSON>,
+        keeper_id: ValidAccountId,
+    ) -> Vec<BatchResultJSON>;
+
+    fn return_keeper_rewards_callback(
+        &mut self,
+        keeper_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+        amount: U128,
+    );
+
+    fn return_referral_rewards_callback(
+        &mut self,
+        referrer_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+        amount: U128,
     );
 
     fn withdraw_kickstarter_fee_callback(
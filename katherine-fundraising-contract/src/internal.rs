@@ -1,6 +1,6 @@
 use crate::*;
 use near_sdk::json_types::U128;
-use near_sdk::{near_bindgen};
+use near_sdk::{near_bindgen, PromiseOrValue};
 
 use crate::interface::*;
 
@@ -12,6 +12,73 @@ impl KatherineFundraising {
             "only allowed for admin"
         );
     }
+
+    pub(crate) fn assert_katherine_fee_percent(katherine_fee_percent: BasisPoints) {
+        assert!(
+            katherine_fee_percent <= MAX_KATHERINE_FEE_PERCENT,
+            "Katherine fee cannot exceed {} basis points",
+            MAX_KATHERINE_FEE_PERCENT
+        );
+    }
+
+    #[inline]
+    pub(crate) fn assert_pause_permission(&self, is_unpausing: bool) {
+        if is_unpausing {
+            self.assert_only_admin();
+        } else {
+            self.assert_only_operator();
+        }
+    }
+
+    pub(crate) fn assert_deposits_not_paused(&self, kickstarter: &Kickstarter) {
+        assert!(
+            !self.deposits_paused && !kickstarter.deposits_paused,
+            "Deposits are paused!"
+        );
+    }
+
+    pub(crate) fn assert_outflows_not_paused(&self, kickstarter: &Kickstarter) {
+        assert!(
+            !self.outflows_paused && !kickstarter.outflows_paused,
+            "Withdrawals and claims are paused!"
+        );
+    }
+
+    /// No active Kickstarters, no Supporters with stNEAR or rewards left, and no interest
+    /// pending for the Kickstarters.
+    pub(crate) fn assert_no_stnear_held(&self) {
+        assert!(
+            self.active_projects.is_empty() && self.supporters.is_empty(),
+            "Kickstarters still hold stNEAR in the current Meta Pool contract!"
+        );
+        assert!(
+            self.kickstarters_with_pending_interest == 0,
+            "{} Kickstarters have stNEAR interest pending to withdraw!",
+            self.kickstarters_with_pending_interest
+        );
+    }
+
+    /// Updates the count of Kickstarters with pending interest, after a change of the unfreeze
+    /// price or of the interest withdrawn.
+    pub(crate) fn internal_update_pending_interest(&mut self, had_pending_interest: bool, kickstarter: &Kickstarter) {
+        match (had_pending_interest, kickstarter.has_pending_interest()) {
+            (false, true) => self.kickstarters_with_pending_interest += 1,
+            (true, false) => self.kickstarters_with_pending_interest -= 1,
+            _ => (),
+        }
+    }
+
+    /// With a keeper bounty, any account could process or unfreeze the Kickstarter.
+    pub(crate) fn is_allowed_keeper(&self, kickstarter: &Kickstarter, account_id: &AccountId) -> bool {
+        kickstarter.keeper_bounty > 0 || self.internal_has_role(Role::Robot, account_id)
+    }
+
+    pub(crate) fn assert_allowed_keeper(&self, kickstarter: &Kickstarter) {
+        assert!(
+            self.is_allowed_keeper(kickstarter, &env::predecessor_account_id()),
+            "only allowed for admin or Robot, the Kickstarter has no keeper bounty"
+        );
+    }
 }
 
 /*************************/
@@ -25,32 +92,74 @@ impl KatherineFundraising {
         tokens_to_release_per_stnear: Balance
     ) -> Balance {
         proportional(
-            kickstarter.total_deposited,
+            kickstarter.get_total_reward_deposits(),
             tokens_to_release_per_stnear,
             NEAR
         )
     }
 
+    /// The fee percent is fixed when the Kickstarter is created.
     pub(crate) fn calculate_katherine_fee(
         &self,
+        kickstarter: &Kickstarter,
         total_tokens_to_release: Balance
     ) -> Balance {
         proportional(
-            self.katherine_fee_percent as u128,
+            kickstarter.katherine_fee_percent as u128,
             total_tokens_to_release,
             BASIS_POINTS
         )
     }
 
-    pub(crate) fn calculate_max_tokens_to_release(
+    /// The referral fee is paid by the Kickstarter, on top of the Supporters rewards.
+    pub(crate) fn calculate_referral_rewards(
         &self,
         kickstarter: &Kickstarter,
+        tokens_to_release: Balance
     ) -> Balance {
         proportional(
+            kickstarter.referral_fee_percent as u128,
+            tokens_to_release,
+            BASIS_POINTS
+        )
+    }
+
+    pub(crate) fn calculate_total_referral_rewards(
+        &self,
+        kickstarter: &Kickstarter,
+        tokens_to_release_per_stnear: Balance
+    ) -> Balance {
+        let referred_tokens_to_release = proportional(
+            kickstarter.total_referred,
+            tokens_to_release_per_stnear,
+            NEAR
+        );
+        self.calculate_referral_rewards(&kickstarter, referred_tokens_to_release)
+    }
+
+    /// In the worst case, all the deposits earn the max tier multiplier.
+    pub(crate) fn calculate_max_tokens_to_release(
+        &self,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        let max_tokens_to_release = proportional(
             kickstarter.deposits_hard_cap,
             kickstarter.max_tokens_to_release_per_stnear,
             NEAR
-        ) 
+        );
+        proportional(max_tokens_to_release, kickstarter.get_max_multiplier(), BASIS_POINTS)
+    }
+
+    /// The Kickstarter must cover the max rewards before receiving Supporters deposits.
+    /// In the worst case, all the Supporters are referred.
+    pub(crate) fn calculate_min_tokens_to_allow_support(
+        &self,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        let max_tokens_to_release = self.calculate_max_tokens_to_release(&kickstarter);
+        max_tokens_to_release
+            + self.calculate_katherine_fee(&kickstarter, max_tokens_to_release)
+            + self.calculate_referral_rewards(&kickstarter, max_tokens_to_release)
     }
 }
 
@@ -70,12 +179,27 @@ impl KatherineFundraising {
         self.kickstarters
             .get(kickstarter_id as u64)
             .expect("Unknown KickstarterId")
+            .into()
+    }
+
+    /// Inner method to store a new or an existing kickstarter with the current version tag.
+    pub(crate) fn internal_save_kickstarter(&mut self, kickstarter: &Kickstarter) {
+        let raw_kickstarter = VersionedKickstarterRef::V2(kickstarter)
+            .try_to_vec()
+            .expect("Kickstarter serialization failed");
+        let index = kickstarter.id as u64;
+        if index == self.kickstarters.len() {
+            self.kickstarters.push_raw(&raw_kickstarter);
+        } else {
+            self.kickstarters.replace_raw(index, &raw_kickstarter);
+        }
     }
 
     pub(crate) fn activate_successful_kickstarter(
         &mut self,
         kickstarter_id: KickstarterIdJSON,
         goal_id: GoalIdJSON,
+        keeper_id: AccountId,
     ) {
         ext_self_metapool::get_st_near_price(
             //promise params
@@ -86,6 +210,7 @@ impl KatherineFundraising {
             ext_self_kickstarter::activate_successful_kickstarter_after(
                 kickstarter_id,
                 goal_id,
+                keeper_id.try_into().unwrap(),
                 //promise params
                 &env::current_account_id(),
                 NO_DEPOSIT,
@@ -100,6 +225,7 @@ impl KatherineFundraising {
         &mut self,
         kickstarter_id: KickstarterIdJSON,
         goal_id: GoalIdJSON,
+        keeper_id: ValidAccountId,
     ) {
         assert_eq!(
             env::promise_results_count(),
@@ -116,34 +242,158 @@ impl KatherineFundraising {
             },
         };
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        match kickstarter.goals.get(goal_id as u64) {
-            None => panic!("Kickstarter did not achieved any goal!"),
-            Some(goal) => {
-                let total_tokens_to_release = self.calculate_total_tokens_to_release(
-                    &kickstarter,
-                    goal.tokens_to_release_per_stnear
-                );
-                let katherine_fee = self.calculate_katherine_fee(total_tokens_to_release);
-                assert!(
-                    kickstarter.available_reward_tokens >= (total_tokens_to_release + katherine_fee),
-                    "Not enough available reward tokens to back the supporters rewards!"
-                );
-                kickstarter.winner_goal_id = Some(goal.id);
-                kickstarter.active = false;
-                self.active_projects.remove(&kickstarter.id);
-                kickstarter.successful = Some(true);
-                kickstarter.katherine_fee = Some(katherine_fee);
-                kickstarter.total_tokens_to_release = Some(total_tokens_to_release);
-                kickstarter.stnear_price_at_freeze = Some(st_near_price.into());
-                self.kickstarters
-                    .replace(kickstarter_id as u64, &kickstarter);
+        if let Err(error) = self.internal_activate_successful_kickstarter(
+            &mut kickstarter,
+            goal_id,
+            st_near_price,
+            keeper_id.as_ref()
+        ) {
+            panic!("{}", error);
+        }
+    }
+
+    /// The activation does not panic, so a batch could continue with the next Kickstarter.
+    pub(crate) fn internal_activate_successful_kickstarter(
+        &mut self,
+        kickstarter: &mut Kickstarter,
+        goal_id: GoalId,
+        st_near_price: Balance,
+        keeper_id: &AccountId,
+    ) -> Result<(), String> {
+        let goal = match kickstarter.get_effective_goal(goal_id) {
+            None => return Err("Kickstarter did not achieved any goal!".to_string()),
+            Some(goal) => goal,
+        };
+        let total_tokens_to_release = self.calculate_total_tokens_to_release(
+            &kickstarter,
+            goal.tokens_to_release_per_stnear
+        );
+        let katherine_fee = self.calculate_katherine_fee(&kickstarter, total_tokens_to_release);
+        let referral_rewards = self.calculate_total_referral_rewards(
+            &kickstarter,
+            goal.tokens_to_release_per_stnear
+        );
+        if kickstarter.available_reward_tokens
+            < (total_tokens_to_release + katherine_fee + referral_rewards)
+        {
+            return Err("Not enough available reward tokens to back the supporters rewards!".to_string());
+        }
+        kickstarter.winner_goal_id = Some(goal.id);
+        kickstarter.active = false;
+        self.active_projects.remove(&kickstarter.id);
+        kickstarter.successful = Some(true);
+        kickstarter.katherine_fee = Some(katherine_fee);
+        kickstarter.total_tokens_to_release = Some(total_tokens_to_release);
+        kickstarter.stnear_price_at_freeze = Some(st_near_price.into());
+        kickstarter.reserve_keeper_bounties();
+        kickstarter.credit_keeper_bounty(keeper_id);
+        self.internal_save_kickstarter(&kickstarter);
+        Ok(())
+    }
+
+    pub(crate) fn internal_deactivate_unsuccessful_kickstarter(&mut self, kickstarter: &mut Kickstarter) {
+        kickstarter.active = false;
+        self.active_projects.remove(&kickstarter.id);
+        kickstarter.successful = Some(false);
+        self.internal_save_kickstarter(&kickstarter);
+    }
+
+    /// The unsuccessful Kickstarters are deactivated right away, the successful ones are
+    /// activated in a single callback with one stNEAR price.
+    pub(crate) fn internal_process_kickstarters(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+    ) -> PromiseOrValue<Vec<BatchResultJSON>> {
+        let keeper_id = env::predecessor_account_id();
+        let mut results: Vec<BatchResultJSON> = Vec::new();
+        let mut kickstarters_to_activate: Vec<KickstarterIdJSON> = Vec::new();
+        for kickstarter_id in kickstarter_ids {
+            let mut kickstarter: Kickstarter = match self.kickstarters.get(kickstarter_id as u64) {
+                Some(kickstarter) => kickstarter.into(),
+                None => {
+                    results.push(BatchResultJSON::failed(kickstarter_id, "Unknown KickstarterId"));
+                    continue;
+                }
+            };
+            if !self.is_allowed_keeper(&kickstarter, &keeper_id) {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "Kickstarter has no keeper bounty"));
+            } else if kickstarter.successful.is_some() {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "kickstarter already activated"));
+            } else if kickstarter.close_timestamp > get_current_epoch_millis() {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "Funding period is not over!"));
+            } else if kickstarter.any_achieved_goal() {
+                kickstarters_to_activate.push(kickstarter_id);
+            } else {
+                self.internal_deactivate_unsuccessful_kickstarter(&mut kickstarter);
+                results.push(BatchResultJSON::successful(kickstarter_id));
+            }
+        }
+        if kickstarters_to_activate.is_empty() {
+            return PromiseOrValue::Value(results);
+        }
+        let gas_for_callback =
+            GAS_FOR_GET_STNEAR + GAS_FOR_BATCH_ITEM * kickstarters_to_activate.len() as Gas;
+        ext_self_metapool::get_st_near_price(
+            &self.metapool_contract_address,
+            NO_DEPOSIT,
+            GAS_FOR_GET_STNEAR
+        ).then(
+            ext_self_kickstarter::process_kickstarters_callback(
+                kickstarters_to_activate,
+                results,
+                keeper_id.try_into().unwrap(),
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                gas_for_callback
+            )
+        ).into()
+    }
+
+    #[private]
+    pub fn process_kickstarters_callback(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+        results: Vec<BatchResultJSON>,
+        keeper_id: ValidAccountId,
+    ) -> Vec<BatchResultJSON> {
+        let mut results = results;
+        let st_near_price = self.internal_get_batch_st_near_price();
+        for kickstarter_id in kickstarter_ids {
+            let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+            if kickstarter.successful.is_some() {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "kickstarter already activated"));
+                continue;
+            }
+            let result = match (st_near_price, kickstarter.get_achieved_goal()) {
+                (None, _) => Err("Meta Pool is not available!".to_string()),
+                (Some(_), None) => Err("Kickstarter did not achieved any goal!".to_string()),
+                (Some(st_near_price), Some(goal)) => {
+                    self.internal_activate_successful_kickstarter(
+                        &mut kickstarter,
+                        goal.id,
+                        st_near_price,
+                        keeper_id.as_ref()
+                    )
+                }
+            };
+            match result {
+                Ok(()) => {
+                    log!("PROCESS: KickstarterId {} was successfully activated", kickstarter_id);
+                    results.push(BatchResultJSON::successful(kickstarter_id));
+                }
+                Err(error) => {
+                    log!("PROCESS: KickstarterId {} failed: {}", kickstarter_id, error);
+                    results.push(BatchResultJSON::failed(kickstarter_id, &error));
+                }
             }
         }
+        results
     }
 
     pub(crate) fn internal_unfreeze_kickstarter_funds(
         &mut self,
-        kickstarter_id: KickstarterId
+        kickstarter_id: KickstarterId,
+        keeper_id: AccountId,
     ) {
         ext_self_metapool::get_st_near_price(
             &self.metapool_contract_address,
@@ -152,6 +402,7 @@ impl KatherineFundraising {
         ).then(
             ext_self_kickstarter::set_stnear_price_at_unfreeze(
                 kickstarter_id,
+                keeper_id.try_into().unwrap(),
                 &env::current_account_id(),
                 NO_DEPOSIT,
                 GAS_FOR_GET_STNEAR
@@ -164,6 +415,7 @@ impl KatherineFundraising {
     pub fn set_stnear_price_at_unfreeze(
         &mut self,
         kickstarter_id: KickstarterIdJSON,
+        keeper_id: ValidAccountId,
     ) {
         assert_eq!(
             env::promise_results_count(),
@@ -180,9 +432,122 @@ impl KatherineFundraising {
             },
         };
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        let had_pending_interest = kickstarter.has_pending_interest();
         kickstarter.stnear_price_at_unfreeze = Some(st_near_price.into());
-        self.kickstarters
-            .replace(kickstarter_id as u64, &kickstarter);
+        kickstarter.credit_keeper_bounty(keeper_id.as_ref());
+        kickstarter.release_keeper_bounties_reserve();
+        self.internal_update_pending_interest(had_pending_interest, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
+    }
+
+    pub(crate) fn internal_unfreeze_kickstarters(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+    ) -> PromiseOrValue<Vec<BatchResultJSON>> {
+        let keeper_id = env::predecessor_account_id();
+        let mut results: Vec<BatchResultJSON> = Vec::new();
+        let mut kickstarters_to_unfreeze: Vec<KickstarterIdJSON> = Vec::new();
+        for kickstarter_id in kickstarter_ids {
+            let kickstarter: Kickstarter = match self.kickstarters.get(kickstarter_id as u64) {
+                Some(kickstarter) => kickstarter.into(),
+                None => {
+                    results.push(BatchResultJSON::failed(kickstarter_id, "Unknown KickstarterId"));
+                    continue;
+                }
+            };
+            if !self.is_allowed_keeper(&kickstarter, &keeper_id) {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "Kickstarter has no keeper bounty"));
+            } else if kickstarter.successful != Some(true) || kickstarter.stnear_price_at_unfreeze.is_some() {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "Nothing to unfreeze"));
+            } else if !kickstarter.funds_can_be_unfreezed() {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "Funds cannot be unfreezed yet"));
+            } else {
+                kickstarters_to_unfreeze.push(kickstarter_id);
+            }
+        }
+        if kickstarters_to_unfreeze.is_empty() {
+            return PromiseOrValue::Value(results);
+        }
+        let gas_for_callback =
+            GAS_FOR_GET_STNEAR + GAS_FOR_BATCH_ITEM * kickstarters_to_unfreeze.len() as Gas;
+        ext_self_metapool::get_st_near_price(
+            &self.metapool_contract_address,
+            NO_DEPOSIT,
+            GAS_FOR_GET_STNEAR
+        ).then(
+            ext_self_kickstarter::unfreeze_kickstarters_callback(
+                kickstarters_to_unfreeze,
+                results,
+                keeper_id.try_into().unwrap(),
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                gas_for_callback
+            )
+        ).into()
+    }
+
+    #[private]
+    pub fn unfreeze_kickstarters_callback(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+        results: Vec<BatchResultJSON>,
+        keeper_id: ValidAccountId,
+    ) -> Vec<BatchResultJSON> {
+        let mut results = results;
+        let st_near_price = self.internal_get_batch_st_near_price();
+        for kickstarter_id in kickstarter_ids {
+            let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+            if kickstarter.stnear_price_at_unfreeze.is_some() {
+                results.push(BatchResultJSON::skipped(kickstarter_id, "Nothing to unfreeze"));
+                continue;
+            }
+            match st_near_price {
+                Some(st_near_price) => {
+                    let had_pending_interest = kickstarter.has_pending_interest();
+                    kickstarter.stnear_price_at_unfreeze = Some(st_near_price);
+                    kickstarter.credit_keeper_bounty(keeper_id.as_ref());
+                    kickstarter.release_keeper_bounties_reserve();
+                    self.internal_update_pending_interest(had_pending_interest, &kickstarter);
+                    self.internal_save_kickstarter(&kickstarter);
+                    log!("UNFREEZE: funds successfully unfreezed for Kickstarter {}", kickstarter_id);
+                    results.push(BatchResultJSON::successful(kickstarter_id));
+                }
+                None => {
+                    results.push(BatchResultJSON::failed(kickstarter_id, "Meta Pool is not available!"));
+                }
+            }
+        }
+        results
+    }
+
+    /// In a batch, a failed price fetch is reported for each Kickstarter instead of panicking.
+    fn internal_get_batch_st_near_price(&self) -> Option<Balance> {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "This is a callback method"
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => None,
+            PromiseResult::Successful(result) => {
+                let price = near_sdk::serde_json::from_slice::<U128>(&result).unwrap();
+                Some(Balance::from(price))
+            },
+        }
+    }
+
+    /// Total rewards of the Supporter for the winner goal, with the tier multipliers.
+    pub(crate) fn calculate_supporter_total_rewards(
+        &self,
+        supporter_id: &SupporterId,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        proportional(
+            kickstarter.get_reward_deposit(&supporter_id),
+            kickstarter.get_winner_goal().tokens_to_release_per_stnear,
+            NEAR
+        )
     }
 
     /// This is the amount of rewards that the supporter could claim regardless of the current timestamp.
@@ -190,30 +555,71 @@ impl KatherineFundraising {
         &self,
         supporter_id: &SupporterId,
         kickstarter: &Kickstarter,
-        tokens_to_release_per_stnear: Balance,
     ) -> Balance {
-        let deposit = kickstarter.get_deposit(&supporter_id);
-        proportional(deposit, tokens_to_release_per_stnear, NEAR)
+        self.calculate_supporter_total_rewards(&supporter_id, &kickstarter)
             - kickstarter.get_rewards_withdraw(&supporter_id)
     }
 
-    pub(crate) fn internal_get_available_rewards(
+    /// This is the amount of rewards that the referrer could claim regardless of the current timestamp.
+    pub(crate) fn internal_get_referrer_rewards(
         &self,
-        supporter_id: &SupporterId,
+        referrer_id: &AccountId,
         kickstarter: &Kickstarter,
     ) -> Balance {
         let goal = kickstarter.get_winner_goal();
-        let deposit = kickstarter.get_deposit(&supporter_id);
-
-        let total_supporter_rewards = proportional(
-            deposit,
+        let referred_tokens_to_release = proportional(
+            kickstarter.get_referred_deposits(&referrer_id),
             goal.tokens_to_release_per_stnear,
             NEAR
         );
-        get_linear_release_proportion(
-            total_supporter_rewards,
-            goal.cliff_timestamp,
-            goal.end_timestamp
-        ).saturating_sub(kickstarter.get_rewards_withdraw(&supporter_id))
+        self.calculate_referral_rewards(&kickstarter, referred_tokens_to_release)
+            - kickstarter.get_referral_rewards_withdraw(&referrer_id)
+    }
+
+    /// The referral rewards are released with the same cliff and end as the Supporters rewards.
+    pub(crate) fn internal_get_available_referral_rewards(
+        &self,
+        referrer_id: &AccountId,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        let goal = kickstarter.get_winner_goal();
+        let total_referrer_rewards = self.internal_get_referrer_rewards(&referrer_id, &kickstarter)
+            + kickstarter.get_referral_rewards_withdraw(&referrer_id);
+        goal.get_released(total_referrer_rewards)
+            .saturating_sub(kickstarter.get_referral_rewards_withdraw(&referrer_id))
+    }
+
+    pub(crate) fn internal_get_available_rewards(
+        &self,
+        supporter_id: &SupporterId,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        let goal = kickstarter.get_winner_goal();
+        let total_supporter_rewards = self.calculate_supporter_total_rewards(&supporter_id, &kickstarter);
+        goal.get_released(total_supporter_rewards)
+            .saturating_sub(kickstarter.get_rewards_withdraw(&supporter_id))
+    }
+
+    /// Cumulative rewards unlocked for the Supporter at each point of the goal vesting schedule.
+    pub(crate) fn internal_get_vesting_schedule(
+        &self,
+        supporter_id: &SupporterId,
+        kickstarter: &Kickstarter,
+    ) -> Vec<VestingUnlockJSON> {
+        let goal = kickstarter.get_winner_goal();
+        let total_supporter_rewards = self.calculate_supporter_total_rewards(&supporter_id, &kickstarter);
+        goal.vesting_schedule
+            .get_timeline(goal.cliff_timestamp, goal.end_timestamp)
+            .into_iter()
+            .map(|timestamp| VestingUnlockJSON {
+                timestamp,
+                unlocked: BalanceJSON::from(goal.vesting_schedule.get_released(
+                    total_supporter_rewards,
+                    goal.cliff_timestamp,
+                    goal.end_timestamp,
+                    timestamp,
+                )),
+            })
+            .collect()
     }
 }
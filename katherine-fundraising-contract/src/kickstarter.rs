@@ -1,8 +1,51 @@
 use crate::*;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{UnorderedMap, Vector};
+use near_sdk::collections::{TreeMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::AccountId;
 
+/// Early-bird tier, the deposits before the end timestamp earn the multiplier.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositTier {
+    pub end_timestamp: EpochMillis,
+    // Rewards multiplier in basis points, 12_000 is +20% pTOKEN.
+    pub multiplier: BasisPoints,
+}
+
+/// In the Step mode, the terms of the highest achieved goal are used. In the Linear mode,
+/// the rewards and the unfreeze date are interpolated up to the next goal.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RewardMode {
+    Step,
+    Linear,
+}
+
+/// Kickstarters are stored with a version tag, so new fields could be added without
+/// breaking the deserialization of the stored ones. To add a new layout, freeze the current
+/// struct in the migration module and add a new variant. V2 is not frozen until its first
+/// deploy, see the migration module.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedKickstarter {
+    V2(Kickstarter),
+}
+
+/// Borrowed version of the VersionedKickstarter, to store a Kickstarter without moving it.
+/// The variants MUST match the VersionedKickstarter variants.
+#[derive(BorshSerialize)]
+pub(crate) enum VersionedKickstarterRef<'a> {
+    V2(&'a Kickstarter),
+}
+
+impl From<VersionedKickstarter> for Kickstarter {
+    fn from(versioned: VersionedKickstarter) -> Self {
+        match versioned {
+            VersionedKickstarter::V2(kickstarter) => kickstarter,
+        }
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Kickstarter {
     // Unique ID identifier
@@ -16,12 +59,16 @@ pub struct Kickstarter {
     pub winner_goal_id: Option<u8>,
     // Katherine fee is denominated in Kickstarter Tokens.
     pub katherine_fee: Option<Balance>,
+    // The fee is kept after the withdraw, it is still part of the committed rewards.
+    pub katherine_fee_withdraw: Balance,
     // This is the Kickstarter Tokens that will be used to pay the Supporters.
     // To make a Kickstarter successful:
     // katherine_fee + total_tokens_to_release > available_reward_tokens
     pub total_tokens_to_release: Option<Balance>,
     // Deposits during the funding period by Supporters.
     pub deposits: UnorderedMap<SupporterId, Balance>,
+    // Number of Supporters with each deposit amount, to get the largest deposit.
+    pub deposit_amounts: TreeMap<Balance, u32>,
     pub rewards_withdraw: UnorderedMap<SupporterId, Balance>,
     pub stnear_withdraw: UnorderedMap<WithdrawEntity, Balance>,
 
@@ -37,6 +84,8 @@ pub struct Kickstarter {
     pub active: bool,
     // True if the kickstart project met the goals
     pub successful: Option<bool>,
+    // Reason of the cancellation, if the project was cancelled by the admin.
+    pub cancel_reason: Option<String>,
     // Spot stnear price at freeze and unfreeze.
     pub stnear_price_at_freeze: Option<Balance>,
     pub stnear_price_at_unfreeze: Option<Balance>,
@@ -52,6 +101,55 @@ pub struct Kickstarter {
     pub available_reward_tokens: Balance,
 
     pub token_contract_decimals: u8,
+    // Katherine fee percent when the Kickstarter was created, later changes are not retroactive.
+    pub katherine_fee_percent: BasisPoints,
+
+    // Emergency stop for this Kickstarter only.
+    pub deposits_paused: bool,
+    pub outflows_paused: bool,
+
+    // Share of the referred Supporters rewards paid to the referrers, on top of the rewards.
+    pub referral_fee_percent: BasisPoints,
+    // Referrer of each Supporter, only the first referrer is kept.
+    pub referrers: UnorderedMap<SupporterId, AccountId>,
+    // stNEAR deposited by the Supporters of each referrer.
+    pub referred_deposits: UnorderedMap<AccountId, Balance>,
+    // stNEAR deposited by each Supporter after naming the referrer.
+    pub supporter_referred_deposits: UnorderedMap<SupporterId, Balance>,
+    pub total_referred: Balance,
+    pub referral_rewards_withdraw: UnorderedMap<AccountId, Balance>,
+
+    // Audit of the deposits made for another account, by (depositor, beneficiary).
+    pub deposits_on_behalf: UnorderedMap<(AccountId, SupporterId), Balance>,
+
+    // Early-bird tiers sorted by end timestamp. After the last tier the multiplier is 1x.
+    pub deposit_tiers: Vec<DepositTier>,
+    // Deposits of each Supporter by tier, the last item is the base tier.
+    pub tier_deposits: UnorderedMap<SupporterId, Vec<Balance>>,
+    // Sum of the Supporters deposits weighted by the tier multipliers.
+    pub total_weighted_deposits: Balance,
+
+    // Reward tokens deposited by each funder, the excedent is refunded pro rata.
+    pub reward_funders: UnorderedMap<AccountId, Balance>,
+    // Besides the owner, only these accounts could fund the rewards.
+    pub funders_allowlist: UnorderedSet<AccountId>,
+
+    pub reward_mode: RewardMode,
+
+    // pTOKEN paid from the Katherine fee to the account that processes or unfreezes the
+    // Kickstarter. With a bounty, the transitions are permissionless.
+    pub keeper_bounty: Balance,
+    pub max_keeper_bounties: Balance,
+    pub total_keeper_bounties: Balance,
+    // Taken from the Katherine fee at the activation, for the bounties up to the unfreeze.
+    pub keeper_bounties_reserve: Balance,
+    pub keeper_rewards: UnorderedMap<AccountId, Balance>,
+
+    // If true, the rewards are split by the deposits weighted by the time they were held
+    // during the funding period. Not compatible with the deposit tiers.
+    pub time_weighted: bool,
+    pub time_weighted_deposits: UnorderedMap<SupporterId, Balance>,
+    pub total_time_weighted_deposits: Balance,
 }
 
 impl Kickstarter {
@@ -80,14 +178,6 @@ impl Kickstarter {
         );
     }
 
-    #[inline]
-    pub(crate) fn assert_number_of_goals(&self, max_number: u8) {
-        assert!(
-            max_number >= self.get_number_of_goals(),
-            "Too many goals, max number is {}", max_number
-        );
-    }
-
     #[inline]
     pub(crate) fn assert_funds_can_be_unfreezed(&self) {
         assert!(
@@ -106,15 +196,9 @@ impl Kickstarter {
     }
 
     #[inline]
-    pub(crate) fn assert_timestamps(&self) {
-        assert!(
-            self.open_timestamp >= get_current_epoch_millis(),
-            "Incorrect open timestamp!"
-        );
-        assert!(
-            self.close_timestamp >= self.open_timestamp,
-            "Incorrect close timestamp!"
-        );
+    #[inline]
+    pub(crate) fn assert_not_cancelled(&self) {
+        assert!(!self.is_cancelled(), "Kickstarter was cancelled!");
     }
 
     #[inline]
@@ -143,6 +227,10 @@ impl Kickstarter {
         self.get_winner_goal().unfreeze_timestamp < get_current_epoch_millis()
     }
 
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_reason.is_some()
+    }
+
     pub fn is_unfreeze(&self) -> bool {
         self.stnear_price_at_unfreeze.is_some()
     }
@@ -157,6 +245,135 @@ impl Kickstarter {
             .expect("Supporter is not part of Kickstarter!")
     }
 
+    /// Index of the tier for a deposit at the timestamp, deposit_tiers.len() is the base tier.
+    pub fn get_tier_index(&self, timestamp: EpochMillis) -> usize {
+        self.deposit_tiers
+            .iter()
+            .position(|tier| timestamp < tier.end_timestamp)
+            .unwrap_or(self.deposit_tiers.len())
+    }
+
+    fn get_tier_multiplier(&self, index: usize) -> u128 {
+        match self.deposit_tiers.get(index) {
+            Some(tier) => tier.multiplier as u128,
+            None => BASIS_POINTS,
+        }
+    }
+
+    /// Max multiplier of the tiers, to cover the worst case rewards.
+    pub fn get_max_multiplier(&self) -> u128 {
+        self.deposit_tiers
+            .iter()
+            .map(|tier| tier.multiplier as u128)
+            .max()
+            .unwrap_or(BASIS_POINTS)
+    }
+
+    /// The deposit weighted by the tier multipliers. The withdrawals are taken from the
+    /// latest tiers, so the deposit is allocated from the earliest tier.
+    pub(crate) fn get_weighted_deposit(&self, supporter_id: &SupporterId, deposit: Balance) -> Balance {
+        if self.deposit_tiers.is_empty() {
+            return deposit;
+        }
+        let tier_deposits = self.tier_deposits.get(&supporter_id).unwrap_or_default();
+        let mut remaining = deposit;
+        let mut weighted: Balance = 0;
+        for (index, amount) in tier_deposits.iter().enumerate() {
+            let amount = std::cmp::min(*amount, remaining);
+            weighted += proportional(amount, self.get_tier_multiplier(index), BASIS_POINTS);
+            remaining -= amount;
+        }
+        weighted + remaining
+    }
+
+    /// The deposit used to calculate the Supporter rewards. In the time-weighted mode, it is
+    /// the time-weighted share of the total deposited.
+    pub fn get_reward_deposit(&self, supporter_id: &SupporterId) -> Balance {
+        if self.time_weighted {
+            if self.total_time_weighted_deposits == 0 {
+                return 0;
+            }
+            return proportional(
+                self.total_deposited,
+                self.get_time_weighted_deposit(&supporter_id),
+                self.total_time_weighted_deposits
+            );
+        }
+        self.get_weighted_deposit(&supporter_id, self.get_deposit(&supporter_id))
+    }
+
+    /// The total deposits used to calculate the total rewards.
+    pub fn get_total_reward_deposits(&self) -> Balance {
+        if self.deposit_tiers.is_empty() {
+            self.total_deposited
+        } else {
+            self.total_weighted_deposits
+        }
+    }
+
+    pub fn get_time_weighted_deposit(&self, supporter_id: &SupporterId) -> Balance {
+        self.time_weighted_deposits.get(&supporter_id).unwrap_or(0)
+    }
+
+    /// An amount held from now to the close timestamp, weighted by the funding period. A
+    /// deposit at the open counts fully, and a last-minute deposit counts almost nothing.
+    fn get_time_weight(&self, amount: Balance) -> Balance {
+        let period = self.close_timestamp.saturating_sub(self.open_timestamp);
+        if period == 0 {
+            return amount;
+        }
+        let remaining = self.close_timestamp.saturating_sub(get_current_epoch_millis());
+        proportional(amount, std::cmp::min(remaining, period) as u128, period as u128)
+    }
+
+    fn update_time_weighted_deposits(&mut self, supporter_id: &AccountId, amount: Balance) {
+        if !self.time_weighted {
+            return;
+        }
+        let weight = self.get_time_weight(amount);
+        let new_weight = self.get_time_weighted_deposit(&supporter_id) + weight;
+        self.time_weighted_deposits.insert(&supporter_id, &new_weight);
+        self.total_time_weighted_deposits += weight;
+    }
+
+    /// The weight is removed in proportion to the withdrawn share of the deposit, so the
+    /// remaining deposit keeps its own time weight. A Supporter that withdraws the whole
+    /// deposit loses the time weight.
+    fn remove_time_weighted_deposits(
+        &mut self,
+        supporter_id: &AccountId,
+        deposit: Balance,
+        amount: Balance,
+    ) -> Balance {
+        if !self.time_weighted {
+            return 0;
+        }
+        let current_weight = self.get_time_weighted_deposit(&supporter_id);
+        let removed = if amount >= deposit {
+            current_weight
+        } else {
+            // The removed weight rounds up, the remaining one must not exceed its share.
+            current_weight - proportional(current_weight, (deposit - amount) as u128, deposit as u128)
+        };
+        if removed == current_weight {
+            self.time_weighted_deposits.remove(&supporter_id);
+        } else {
+            self.time_weighted_deposits.insert(&supporter_id, &(current_weight - removed));
+        }
+        self.total_time_weighted_deposits -= removed;
+        removed
+    }
+
+    /// The removed weight is added back as it was, not weighted again at the restore time.
+    fn restore_time_weighted_deposits(&mut self, supporter_id: &AccountId, weight: Balance) {
+        if weight == 0 {
+            return;
+        }
+        let new_weight = self.get_time_weighted_deposit(&supporter_id) + weight;
+        self.time_weighted_deposits.insert(&supporter_id, &new_weight);
+        self.total_time_weighted_deposits += weight;
+    }
+
     pub fn get_at_freeze_deposits_in_near(&self, supporter_id: &SupporterId) -> Option<BalanceJSON> {
         if let Some(st_near_price) = self.stnear_price_at_freeze {
             let deposit = self.get_deposit(&supporter_id);
@@ -181,6 +398,20 @@ impl Kickstarter {
         }
     }
 
+    pub fn get_referred_deposits(&self, referrer_id: &AccountId) -> Balance {
+        match self.referred_deposits.get(&referrer_id) {
+            Some(amount) => amount,
+            None => 0,
+        }
+    }
+
+    pub fn get_referral_rewards_withdraw(&self, referrer_id: &AccountId) -> Balance {
+        match self.referral_rewards_withdraw.get(&referrer_id) {
+            Some(amount) => amount,
+            None => 0,
+        }
+    }
+
     pub fn get_stnear_withdraw(&self, entity: &WithdrawEntity) -> Balance {
         match self.stnear_withdraw.get(&entity) {
             Some(amount) => amount,
@@ -188,11 +419,48 @@ impl Kickstarter {
         }
     }
 
+    /// Besides the desired amount, a goal could require a min number of Supporters and a
+    /// max share for the largest deposit.
+    pub fn is_goal_achieved(&self, goal: &Goal, max_deposit: &mut Option<Balance>) -> bool {
+        if goal.desired_amount > self.total_deposited
+            || self.get_total_supporters() < goal.min_supporters
+        {
+            return false;
+        }
+        match goal.max_supporter_share {
+            Some(max_share) => {
+                // The largest deposit is only calculated once, and only when it is required.
+                let max_deposit = *max_deposit.get_or_insert_with(|| self.get_max_deposit());
+                proportional(self.total_deposited, max_share as u128, BASIS_POINTS) >= max_deposit
+            }
+            None => true,
+        }
+    }
+
+    fn get_max_deposit(&self) -> Balance {
+        self.deposit_amounts.max().unwrap_or(0)
+    }
+
+    /// Like the total deposited, the amounts are only updated during the funding period.
+    fn update_deposit_amounts(&mut self, old_deposit: Balance, new_deposit: Balance) {
+        if old_deposit > 0 {
+            match self.deposit_amounts.get(&old_deposit).unwrap_or(0) {
+                0 | 1 => self.deposit_amounts.remove(&old_deposit),
+                count => self.deposit_amounts.insert(&old_deposit, &(count - 1)),
+            };
+        }
+        if new_deposit > 0 {
+            let count = self.deposit_amounts.get(&new_deposit).unwrap_or(0);
+            self.deposit_amounts.insert(&new_deposit, &(count + 1));
+        }
+    }
+
     pub fn get_achieved_goal(&mut self) -> Option<Goal> {
+        let mut max_deposit: Option<Balance> = None;
         let mut achieved_goals: Vec<Goal> = self
             .goals
             .iter()
-            .filter(|goal| goal.desired_amount <= self.total_deposited)
+            .filter(|goal| self.is_goal_achieved(goal, &mut max_deposit))
             .collect();
         if achieved_goals.len() > 0 {
             achieved_goals.sort_by_key(|goal| goal.desired_amount);
@@ -205,17 +473,68 @@ impl Kickstarter {
     }
 
     pub fn any_achieved_goal(&self) -> bool {
+        let mut max_deposit: Option<Balance> = None;
         self.goals
             .iter()
-            .any(|goal| goal.desired_amount <= self.total_deposited)
+            .any(|goal| self.is_goal_achieved(&goal, &mut max_deposit))
     }
 
+    /// The winner goal with the effective terms of the reward mode.
     pub fn get_winner_goal(&self) -> Goal {
-        self.goals
-            .get(self.winner_goal_id.expect("No goal defined") as u64)
+        self.get_effective_goal(self.winner_goal_id.expect("No goal defined"))
             .expect("Incorrect goal index")
     }
 
+    /// In the Linear mode, the goal terms are interpolated with the next goal using the
+    /// total deposited. The cliff and end timestamps are not interpolated.
+    pub fn get_effective_goal(&self, goal_id: GoalId) -> Option<Goal> {
+        let goal = self.goals.get(goal_id as u64)?;
+        if self.reward_mode == RewardMode::Step {
+            return Some(goal);
+        }
+        match self.goals.get(goal_id as u64 + 1) {
+            // If the next desired amount is reached, the next goal failed other conditions.
+            Some(next_goal)
+                if next_goal.desired_amount > goal.desired_amount
+                    && next_goal.desired_amount > self.total_deposited =>
+            {
+                let range = next_goal.desired_amount - goal.desired_amount;
+                let progress = self.total_deposited.saturating_sub(goal.desired_amount);
+                let tokens_to_release_per_stnear = goal.tokens_to_release_per_stnear
+                    + proportional(
+                        next_goal.tokens_to_release_per_stnear - goal.tokens_to_release_per_stnear,
+                        progress,
+                        range
+                    );
+                let unfreeze_timestamp = goal.unfreeze_timestamp
+                    - proportional(
+                        (goal.unfreeze_timestamp - next_goal.unfreeze_timestamp) as u128,
+                        progress,
+                        range
+                    ) as EpochMillis;
+                Some(Goal {
+                    tokens_to_release_per_stnear,
+                    unfreeze_timestamp,
+                    ..goal
+                })
+            }
+            _ => Some(goal),
+        }
+    }
+
+    /// Effective terms of the winner goal or, before the activation, of the achieved goal.
+    fn get_effective_terms(&self) -> Option<Goal> {
+        match self.winner_goal_id {
+            Some(goal_id) => self.get_effective_goal(goal_id),
+            None => self
+                .goals
+                .iter()
+                .filter(|goal| self.is_goal_achieved(goal, &mut None))
+                .max_by_key(|goal| goal.desired_amount)
+                .and_then(|goal| self.get_effective_goal(goal.id)),
+        }
+    }
+
     pub fn get_goal_by_id(&self, goal_id: GoalId) -> Goal {
         self.goals.get(goal_id as u64).expect("Goal not found!")
     }
@@ -225,10 +544,224 @@ impl Kickstarter {
             Some(total) => total,
             None => 0,
         };
+        self.update_tier_deposits(&supporter_id, current_supporter_deposit, *amount);
+        self.update_time_weighted_deposits(&supporter_id, *amount);
         let new_total: Balance = current_supporter_deposit + amount;
+        self.update_deposit_amounts(current_supporter_deposit, new_total);
         self.deposits.insert(&supporter_id, &new_total);
     }
 
+    fn update_tier_deposits(&mut self, supporter_id: &AccountId, current_deposit: Balance, amount: Balance) {
+        if self.deposit_tiers.is_empty() {
+            return;
+        }
+        let mut tier_deposits = self
+            .tier_deposits
+            .get(&supporter_id)
+            .unwrap_or(vec![0; self.deposit_tiers.len() + 1]);
+        trim_tier_deposits(&mut tier_deposits, current_deposit);
+        let weighted_before = self.get_weighted_deposit(&supporter_id, current_deposit);
+        tier_deposits[self.get_tier_index(get_current_epoch_millis())] += amount;
+        self.tier_deposits.insert(&supporter_id, &tier_deposits);
+        self.total_weighted_deposits +=
+            self.get_weighted_deposit(&supporter_id, current_deposit + amount) - weighted_before;
+    }
+
+    /// Only during the funding period, the total deposits are kept after the evaluation.
+    /// Returns the removed deposits, to restore them if the withdraw fails.
+    pub(crate) fn remove_weighted_deposits(
+        &mut self,
+        supporter_id: &AccountId,
+        deposit: Balance,
+        amount: Balance,
+    ) -> WithdrawnDepositsJSON {
+        let mut withdrawn_tier_deposits: Vec<BalanceJSON> = Vec::new();
+        if !self.deposit_tiers.is_empty() {
+            let new_deposit = deposit - amount;
+            let removed = self.get_weighted_deposit(&supporter_id, deposit)
+                - self.get_weighted_deposit(&supporter_id, new_deposit);
+            self.total_weighted_deposits -= removed;
+            if let Some(mut tier_deposits) = self.tier_deposits.get(&supporter_id) {
+                trim_tier_deposits(&mut tier_deposits, deposit);
+                let tier_deposits_before = tier_deposits.clone();
+                trim_tier_deposits(&mut tier_deposits, new_deposit);
+                withdrawn_tier_deposits = tier_deposits_before
+                    .iter()
+                    .zip(tier_deposits.iter())
+                    .map(|(before, after)| BalanceJSON::from(before - after))
+                    .collect();
+                if new_deposit == 0 {
+                    self.tier_deposits.remove(&supporter_id);
+                } else {
+                    self.tier_deposits.insert(&supporter_id, &tier_deposits);
+                }
+            }
+        }
+        let withdrawn_time_weight = self.remove_time_weighted_deposits(&supporter_id, deposit, amount);
+        self.update_deposit_amounts(deposit, deposit - amount);
+        WithdrawnDepositsJSON {
+            tier_deposits: withdrawn_tier_deposits,
+            time_weighted_deposit: BalanceJSON::from(withdrawn_time_weight),
+            referred_deposit: BalanceJSON::from(0),
+        }
+    }
+
+    /// Adds back the deposits removed by a failed withdraw, the current deposit does not
+    /// include the withdrawn amount yet.
+    pub(crate) fn restore_weighted_deposits(
+        &mut self,
+        supporter_id: &AccountId,
+        current_deposit: Balance,
+        amount: Balance,
+        withdrawn: &WithdrawnDepositsJSON,
+    ) {
+        if !withdrawn.tier_deposits.is_empty() {
+            let mut tier_deposits = self
+                .tier_deposits
+                .get(&supporter_id)
+                .unwrap_or(vec![0; withdrawn.tier_deposits.len()]);
+            trim_tier_deposits(&mut tier_deposits, current_deposit);
+            let weighted_before = self.get_weighted_deposit(&supporter_id, current_deposit);
+            for (tier_deposit, removed) in tier_deposits.iter_mut().zip(withdrawn.tier_deposits.iter()) {
+                *tier_deposit += removed.0;
+            }
+            self.tier_deposits.insert(&supporter_id, &tier_deposits);
+            self.total_weighted_deposits +=
+                self.get_weighted_deposit(&supporter_id, current_deposit + amount) - weighted_before;
+        }
+        self.restore_time_weighted_deposits(&supporter_id, withdrawn.time_weighted_deposit.0);
+        self.update_deposit_amounts(current_deposit, current_deposit + amount);
+    }
+
+    pub(crate) fn record_deposit_on_behalf(
+        &mut self,
+        depositor_id: &AccountId,
+        supporter_id: &SupporterId,
+        amount: Balance,
+    ) {
+        let key = (depositor_id.clone(), supporter_id.clone());
+        let new_total = self.deposits_on_behalf.get(&key).unwrap_or(0) + amount;
+        self.deposits_on_behalf.insert(&key, &new_total);
+    }
+
+    /// The deposit is counted for the first referrer of the Supporter. Referrals are ignored
+    /// if the Kickstarter does not pay a referral fee.
+    pub(crate) fn update_referred_deposits(
+        &mut self,
+        supporter_id: &AccountId,
+        referrer_id: Option<AccountId>,
+        amount: Balance,
+    ) {
+        if self.referral_fee_percent == 0 {
+            return;
+        }
+        let referrer_id = match self.referrers.get(&supporter_id) {
+            Some(current_referrer_id) => current_referrer_id,
+            None => match referrer_id {
+                Some(referrer_id) if &referrer_id != supporter_id => {
+                    self.referrers.insert(&supporter_id, &referrer_id);
+                    log!("REFERRAL: {} referred by {}", supporter_id, referrer_id);
+                    referrer_id
+                }
+                _ => return,
+            },
+        };
+        let new_total = self.get_referred_deposits(&referrer_id) + amount;
+        self.referred_deposits.insert(&referrer_id, &new_total);
+        let supporter_referred = self.get_supporter_referred_deposit(&supporter_id) + amount;
+        self.supporter_referred_deposits.insert(&supporter_id, &supporter_referred);
+        self.total_referred += amount;
+    }
+
+    pub fn get_supporter_referred_deposit(&self, supporter_id: &SupporterId) -> Balance {
+        self.supporter_referred_deposits.get(&supporter_id).unwrap_or(0)
+    }
+
+    /// The withdrawals are taken from the latest deposits, so the referred ones go first. The
+    /// deposits made before naming the referrer were never credited. Returns the removed amount.
+    pub(crate) fn remove_referred_deposits(&mut self, supporter_id: &AccountId, amount: Balance) -> Balance {
+        let supporter_referred = self.get_supporter_referred_deposit(&supporter_id);
+        if supporter_referred == 0 {
+            return 0;
+        }
+        let referrer_id = self.referrers.get(&supporter_id).expect("Referrer not found!");
+        let removed = std::cmp::min(amount, supporter_referred);
+        if removed == supporter_referred {
+            self.supporter_referred_deposits.remove(&supporter_id);
+        } else {
+            self.supporter_referred_deposits.insert(&supporter_id, &(supporter_referred - removed));
+        }
+        let new_total = self.get_referred_deposits(&referrer_id) - removed;
+        if new_total == 0 {
+            self.referred_deposits.remove(&referrer_id);
+        } else {
+            self.referred_deposits.insert(&referrer_id, &new_total);
+        }
+        self.total_referred -= removed;
+        removed
+    }
+
+    pub fn is_allowed_reward_funder(&self, funder_id: &AccountId) -> bool {
+        &self.owner_id == funder_id || self.funders_allowlist.contains(funder_id)
+    }
+
+    /// The owner is not counted, so the allowed funders cannot block the owner funding.
+    pub(crate) fn update_reward_funders(&mut self, funder_id: &AccountId, amount: Balance) {
+        let current_funding = self.reward_funders.get(funder_id).unwrap_or(0);
+        if current_funding == 0 && funder_id != &self.owner_id {
+            let owner_funder = if self.reward_funders.get(&self.owner_id).is_some() { 1 } else { 0 };
+            assert!(
+                self.reward_funders.len() - owner_funder < MAX_REWARD_FUNDERS,
+                "Too many reward funders, max number is {} besides the owner",
+                MAX_REWARD_FUNDERS
+            );
+        }
+        self.reward_funders.insert(funder_id, &(current_funding + amount));
+    }
+
+    /// The Katherine fee that the fee collector could currently withdraw.
+    pub fn get_available_katherine_fee(&self) -> Balance {
+        self.katherine_fee.unwrap_or(0).saturating_sub(self.katherine_fee_withdraw)
+    }
+
+    pub fn get_keeper_rewards(&self, keeper_id: &AccountId) -> Balance {
+        self.keeper_rewards.get(keeper_id).unwrap_or(0)
+    }
+
+    /// At the activation, the bounties up to the Kickstarter cap are taken from the Katherine
+    /// fee, so the fee collector cannot withdraw them before the unfreeze.
+    pub(crate) fn reserve_keeper_bounties(&mut self) {
+        let katherine_fee = self.katherine_fee.unwrap_or(0);
+        let reserve = std::cmp::min(
+            self.max_keeper_bounties.saturating_sub(self.total_keeper_bounties),
+            katherine_fee,
+        );
+        self.katherine_fee = Some(katherine_fee - reserve);
+        self.keeper_bounties_reserve += reserve;
+    }
+
+    /// The unfreeze is the last transition, the unused reserve goes back to the Katherine fee.
+    /// The withdrawn fee is tracked apart, so the fee collector could withdraw the released
+    /// reserve even after withdrawing the rest of the fee.
+    pub(crate) fn release_keeper_bounties_reserve(&mut self) {
+        if self.keeper_bounties_reserve > 0 {
+            self.katherine_fee = Some(self.katherine_fee.unwrap_or(0) + self.keeper_bounties_reserve);
+            self.keeper_bounties_reserve = 0;
+        }
+    }
+
+    /// The bounty is limited by the reserve taken from the Katherine fee.
+    pub(crate) fn credit_keeper_bounty(&mut self, keeper_id: &AccountId) -> Balance {
+        let bounty = std::cmp::min(self.keeper_bounty, self.keeper_bounties_reserve);
+        if bounty > 0 {
+            self.keeper_bounties_reserve -= bounty;
+            self.total_keeper_bounties += bounty;
+            self.keeper_rewards.insert(keeper_id, &(self.get_keeper_rewards(keeper_id) + bounty));
+            log!("KEEPER: {} pTOKEN credited to {} for KickstarterId {}", bounty, keeper_id, self.id);
+        }
+        bounty
+    }
+
     pub fn get_number_of_goals(&self) -> u8 {
         self.goals.len() as u8
     }
@@ -245,7 +778,7 @@ impl Kickstarter {
         }
     }
 
-    pub fn to_details_json(&self) -> KickstarterDetailsJSON {
+    pub fn to_details_json(&self, metadata: Option<KickstarterMetadata>) -> KickstarterDetailsJSON {
         let mut goals: Vec<GoalJSON> = Vec::new();
         for goal in self.goals.iter() {
             goals.push(goal.to_json());
@@ -258,6 +791,7 @@ impl Kickstarter {
             Some(balance) => Some(BalanceJSON::from(balance)),
             None => None,
         };
+        let effective_goal = self.get_effective_terms();
         KickstarterDetailsJSON {
             id: self.id.into(),
             total_supporters: self.deposits.len() as u32,
@@ -270,9 +804,25 @@ impl Kickstarter {
             goals,
             active: self.active,
             successful: self.successful,
+            cancelled: self.is_cancelled(),
+            cancel_reason: self.cancel_reason.clone(),
             winner_goal_id: self.winner_goal_id,
             enough_reward_tokens: self.enough_reward_tokens,
             available_reward_tokens: BalanceJSON::from(self.available_reward_tokens),
+            katherine_fee_percent: self.katherine_fee_percent,
+            deposits_paused: self.deposits_paused,
+            outflows_paused: self.outflows_paused,
+            referral_fee_percent: self.referral_fee_percent,
+            total_referred: BalanceJSON::from(self.total_referred),
+            deposit_tiers: self.deposit_tiers.clone(),
+            reward_mode: self.reward_mode,
+            keeper_bounty: BalanceJSON::from(self.keeper_bounty),
+            time_weighted: self.time_weighted,
+            effective_tokens_to_release_per_stnear: effective_goal
+                .as_ref()
+                .map(|goal| BalanceJSON::from(goal.tokens_to_release_per_stnear)),
+            effective_unfreeze_timestamp: effective_goal.map(|goal| goal.unfreeze_timestamp),
+            metadata,
         }
     }
 
@@ -328,8 +878,10 @@ impl KatherineFundraising {
             goals: Vector::new(Keys::Goals.as_prefix(&id.to_string()).as_bytes()),
             winner_goal_id: None,
             katherine_fee: None,
+            katherine_fee_withdraw: 0,
             total_tokens_to_release: None,
             deposits: UnorderedMap::new(Keys::Deposits.as_prefix(&id.to_string()).as_bytes()),
+            deposit_amounts: TreeMap::new(Keys::DepositAmounts.as_prefix(&id.to_string()).as_bytes()),
             rewards_withdraw: UnorderedMap::new(
                 Keys::RewardWithdraws.as_prefix(&id.to_string()).as_bytes(),
             ),
@@ -343,6 +895,7 @@ impl KatherineFundraising {
             owner_id,
             active: true,
             successful: None,
+            cancel_reason: None,
             stnear_price_at_freeze: None,
             stnear_price_at_unfreeze: None,
             creation_timestamp: get_current_epoch_millis(),
@@ -351,9 +904,45 @@ impl KatherineFundraising {
             token_contract_address,
             token_contract_decimals,
             available_reward_tokens: 0,
+            katherine_fee_percent: self.katherine_fee_percent,
+            deposits_paused: false,
+            outflows_paused: false,
+            referral_fee_percent: 0,
+            referrers: UnorderedMap::new(Keys::Referrers.as_prefix(&id.to_string()).as_bytes()),
+            referred_deposits: UnorderedMap::new(
+                Keys::ReferredDeposits.as_prefix(&id.to_string()).as_bytes(),
+            ),
+            supporter_referred_deposits: UnorderedMap::new(
+                Keys::SupporterReferredDeposits.as_prefix(&id.to_string()).as_bytes(),
+            ),
+            total_referred: 0,
+            referral_rewards_withdraw: UnorderedMap::new(
+                Keys::ReferralWithdraws.as_prefix(&id.to_string()).as_bytes(),
+            ),
+            deposits_on_behalf: UnorderedMap::new(
+                Keys::DepositsOnBehalf.as_prefix(&id.to_string()).as_bytes(),
+            ),
+            deposit_tiers: Vec::new(),
+            tier_deposits: UnorderedMap::new(Keys::TierDeposits.as_prefix(&id.to_string()).as_bytes()),
+            total_weighted_deposits: 0,
+            reward_funders: UnorderedMap::new(Keys::RewardFunders.as_prefix(&id.to_string()).as_bytes()),
+            funders_allowlist: UnorderedSet::new(
+                Keys::FundersAllowlist.as_prefix(&id.to_string()).as_bytes(),
+            ),
+            reward_mode: RewardMode::Step,
+            keeper_bounty: 0,
+            max_keeper_bounties: 0,
+            total_keeper_bounties: 0,
+            keeper_bounties_reserve: 0,
+            keeper_rewards: UnorderedMap::new(Keys::KeeperRewards.as_prefix(&id.to_string()).as_bytes()),
+            time_weighted: false,
+            time_weighted_deposits: UnorderedMap::new(
+                Keys::TimeWeightedDeposits.as_prefix(&id.to_string()).as_bytes(),
+            ),
+            total_time_weighted_deposits: 0,
         };
-        kickstarter.assert_timestamps();
-        self.kickstarters.push(&kickstarter);
+        self.assert_valid_config(&kickstarter.to_config());
+        self.internal_save_kickstarter(&kickstarter);
         self.kickstarter_id_by_slug
             .insert(&kickstarter.slug, &kickstarter.id);
         self.active_projects.insert(&kickstarter.id);
@@ -362,58 +951,347 @@ impl KatherineFundraising {
 
     pub(crate) fn internal_update_kickstarter(
         &mut self,
-        old_kickstarter: Kickstarter,
-        name: String,
-        slug: String,
-        owner_id: AccountId,
-        open_timestamp: EpochMillis,
-        close_timestamp: EpochMillis,
-        token_contract_address: AccountId,
-        deposits_hard_cap: BalanceJSON,
-        max_tokens_to_release_per_stnear: BalanceJSON,
-        token_contract_decimals: u8
+        kickstarter: &mut Kickstarter,
+        name: Option<String>,
+        slug: Option<String>,
+        owner_id: Option<AccountId>,
+        open_timestamp: Option<EpochMillis>,
+        close_timestamp: Option<EpochMillis>,
+        token_contract_address: Option<AccountId>,
+        deposits_hard_cap: Option<BalanceJSON>,
+        max_tokens_to_release_per_stnear: Option<BalanceJSON>,
+        token_contract_decimals: Option<u8>
     ) {
         assert!(
-            old_kickstarter.open_timestamp >= get_current_epoch_millis(),
+            kickstarter.open_timestamp >= get_current_epoch_millis(),
             "Changes are not allow after the funding period started!"
         );
+        kickstarter.assert_not_cancelled();
 
-        let id = old_kickstarter.id;
-        let kickstarter = Kickstarter {
-            id,
-            name,
-            slug,
-            goals: Vector::new(Keys::Goals.as_prefix(&id.to_string()).as_bytes()),
-            winner_goal_id: None,
-            katherine_fee: None,
-            total_tokens_to_release: None,
-            deposits: UnorderedMap::new(Keys::Deposits.as_prefix(&id.to_string()).as_bytes()),
-            rewards_withdraw: UnorderedMap::new(
-                Keys::RewardWithdraws.as_prefix(&id.to_string()).as_bytes(),
-            ),
-            stnear_withdraw: UnorderedMap::new(
-                Keys::StnearWithdraws.as_prefix(&id.to_string()).as_bytes(),
-            ),
-            total_deposited: 0,
-            deposits_hard_cap: Balance::from(deposits_hard_cap),
-            max_tokens_to_release_per_stnear: Balance::from(max_tokens_to_release_per_stnear),
-            enough_reward_tokens: false,
-            owner_id,
-            active: true,
-            successful: None,
-            stnear_price_at_freeze: None,
-            stnear_price_at_unfreeze: None,
-            creation_timestamp: get_current_epoch_millis(),
-            open_timestamp,
-            close_timestamp,
-            token_contract_address,
-            token_contract_decimals,
-            available_reward_tokens: 0,
+        if let Some(name) = name {
+            kickstarter.name = name;
+        }
+        if let Some(slug) = slug {
+            if slug != kickstarter.slug {
+                self.assert_unique_slug(&slug);
+                self.kickstarter_id_by_slug.remove(&kickstarter.slug);
+                self.kickstarter_id_by_slug.insert(&slug, &kickstarter.id);
+                kickstarter.slug = slug;
+            }
+        }
+        if let Some(owner_id) = owner_id {
+            kickstarter.owner_id = owner_id;
+        }
+        if let Some(open_timestamp) = open_timestamp {
+            kickstarter.open_timestamp = open_timestamp;
+        }
+        if let Some(close_timestamp) = close_timestamp {
+            kickstarter.close_timestamp = close_timestamp;
+        }
+        if token_contract_address.is_some() || token_contract_decimals.is_some() {
+            assert_eq!(
+                kickstarter.available_reward_tokens,
+                0,
+                "The Kickstarter Token cannot be changed after the reward tokens were deposited!"
+            );
+            if let Some(token_contract_address) = token_contract_address {
+                kickstarter.token_contract_address = token_contract_address;
+            }
+            if let Some(token_contract_decimals) = token_contract_decimals {
+                kickstarter.token_contract_decimals = token_contract_decimals;
+            }
+        }
+        if let Some(deposits_hard_cap) = deposits_hard_cap {
+            kickstarter.deposits_hard_cap = Balance::from(deposits_hard_cap);
+        }
+        if let Some(max_tokens_to_release_per_stnear) = max_tokens_to_release_per_stnear {
+            kickstarter.max_tokens_to_release_per_stnear = Balance::from(max_tokens_to_release_per_stnear);
+        }
+        self.assert_valid_config(&kickstarter.to_config());
+
+        // The hard cap or the max tokens to release could change the required reward tokens.
+        kickstarter.enough_reward_tokens = {
+            kickstarter.available_reward_tokens >= self.calculate_min_tokens_to_allow_support(&kickstarter)
         };
-        kickstarter.assert_timestamps();
-        self.kickstarters.replace(id as u64, &kickstarter);
-        self.kickstarter_id_by_slug.remove(&old_kickstarter.slug);
-        self.kickstarter_id_by_slug
-            .insert(&kickstarter.slug, &kickstarter.id);
+        self.internal_save_kickstarter(&kickstarter);
+        log!("UPDATE: KickstarterId {} updated", kickstarter.id);
+    }
+}
+
+/// Removes the withdrawals from the latest tiers, the deposit is kept in the earliest ones.
+fn trim_tier_deposits(tier_deposits: &mut Vec<Balance>, deposit: Balance) {
+    let mut remaining = deposit;
+    for tier_deposit in tier_deposits.iter_mut() {
+        *tier_deposit = std::cmp::min(*tier_deposit, remaining);
+        remaining -= *tier_deposit;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::tests::unit_test_utils::*;
+
+    #[test]
+    fn test_referred_deposits_before_the_referrer() {
+        let (_context, _contract, mut kickstarter) = kickstarter_only_setup();
+        kickstarter.referral_fee_percent = 500;
+        let referrer_id = REFERRER_ACCOUNT.to_string();
+
+        // Deposits of a Supporter without a referrer and of a referred one.
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        kickstarter.update_referred_deposits(&supporter_id, None, 5 * NEAR);
+        kickstarter.update_referred_deposits(&supporter_id, Some(referrer_id.clone()), 3 * NEAR);
+        let other_id = OTHER_SUPPORTER_ACCOUNT.to_string();
+        kickstarter.update_referred_deposits(&other_id, Some(referrer_id.clone()), 2 * NEAR);
+        assert_eq!(kickstarter.get_referred_deposits(&referrer_id), 5 * NEAR);
+
+        // Only the referred deposits of the Supporter are removed.
+        kickstarter.remove_referred_deposits(&supporter_id, 8 * NEAR);
+        assert_eq!(kickstarter.get_referred_deposits(&referrer_id), 2 * NEAR);
+        assert_eq!(kickstarter.total_referred, 2 * NEAR);
+    }
+
+    #[test]
+    fn test_time_weighted_withdrawals() {
+        let (mut context, _contract, mut kickstarter) = kickstarter_only_setup();
+        kickstarter.time_weighted = true;
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        let other_id = OTHER_SUPPORTER_ACCOUNT.to_string();
+
+        // A deposit at the open counts fully, a deposit at the middle counts half.
+        set_timestamp(&mut context, kickstarter.open_timestamp);
+        kickstarter.update_supporter_deposits(&supporter_id, &(100 * NEAR));
+        set_timestamp(&mut context, (kickstarter.open_timestamp + kickstarter.close_timestamp) / 2);
+        kickstarter.update_supporter_deposits(&other_id, &(100 * NEAR));
+        assert_eq!(kickstarter.get_time_weighted_deposit(&supporter_id), 100 * NEAR);
+        assert_eq!(kickstarter.total_time_weighted_deposits, 150 * NEAR);
+
+        // A partial withdraw before the close removes the withdrawn share of the weight.
+        set_timestamp(&mut context, kickstarter.close_timestamp - 1);
+        kickstarter.remove_weighted_deposits(&supporter_id, 100 * NEAR, 99 * NEAR);
+        assert_eq!(kickstarter.get_time_weighted_deposit(&supporter_id), NEAR);
+        assert_eq!(kickstarter.total_time_weighted_deposits, 51 * NEAR);
+
+        // A full withdraw removes all the weight.
+        kickstarter.remove_weighted_deposits(&supporter_id, NEAR, NEAR);
+        assert_eq!(kickstarter.get_time_weighted_deposit(&supporter_id), 0);
+        assert_eq!(kickstarter.total_time_weighted_deposits, 50 * NEAR);
+    }
+
+    /// Two early-bird tiers, of one day each from the open.
+    fn set_deposit_tiers(kickstarter: &mut Kickstarter) {
+        kickstarter.deposit_tiers = vec![
+            DepositTier { end_timestamp: kickstarter.open_timestamp + DAY_MILLIS, multiplier: 15_000 },
+            DepositTier { end_timestamp: kickstarter.open_timestamp + 2 * DAY_MILLIS, multiplier: 12_000 },
+        ];
+    }
+
+    fn withdraw(kickstarter: &mut Kickstarter, supporter_id: &SupporterId, amount: Balance) {
+        let deposit = kickstarter.get_deposit(&supporter_id);
+        if deposit == amount {
+            kickstarter.deposits.remove(&supporter_id);
+        } else {
+            kickstarter.deposits.insert(&supporter_id, &(deposit - amount));
+        }
+        kickstarter.remove_weighted_deposits(&supporter_id, deposit, amount);
+    }
+
+    #[test]
+    fn test_weighted_deposit_tier_boundaries() {
+        let (mut context, _contract, mut kickstarter) = kickstarter_only_setup();
+        set_deposit_tiers(&mut kickstarter);
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        let open_timestamp = kickstarter.open_timestamp;
+
+        // A tier ends at its end timestamp, the next deposit is in the following tier.
+        assert_eq!(kickstarter.get_tier_index(open_timestamp), 0);
+        assert_eq!(kickstarter.get_tier_index(open_timestamp + DAY_MILLIS - 1), 0);
+        assert_eq!(kickstarter.get_tier_index(open_timestamp + DAY_MILLIS), 1);
+        assert_eq!(kickstarter.get_tier_index(open_timestamp + 2 * DAY_MILLIS), 2);
+
+        set_timestamp(&mut context, open_timestamp);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+        set_timestamp(&mut context, open_timestamp + DAY_MILLIS);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+        set_timestamp(&mut context, open_timestamp + 2 * DAY_MILLIS);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+
+        assert_eq!(
+            kickstarter.tier_deposits.get(&supporter_id).unwrap(),
+            vec![10 * NEAR, 10 * NEAR, 10 * NEAR]
+        );
+        assert_eq!(kickstarter.total_weighted_deposits, 37 * NEAR);
+        assert_eq!(kickstarter.get_weighted_deposit(&supporter_id, 30 * NEAR), 37 * NEAR);
+        // The deposit is allocated from the earliest tier.
+        assert_eq!(kickstarter.get_weighted_deposit(&supporter_id, 15 * NEAR), 21 * NEAR);
+        // Without tier deposits, the deposit is not weighted.
+        let other_id = OTHER_SUPPORTER_ACCOUNT.to_string();
+        assert_eq!(kickstarter.get_weighted_deposit(&other_id, 15 * NEAR), 15 * NEAR);
+    }
+
+    #[test]
+    fn test_weighted_deposit_partial_withdrawals() {
+        let (mut context, _contract, mut kickstarter) = kickstarter_only_setup();
+        set_deposit_tiers(&mut kickstarter);
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        let open_timestamp = kickstarter.open_timestamp;
+        set_timestamp(&mut context, open_timestamp);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+        set_timestamp(&mut context, open_timestamp + DAY_MILLIS);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+        set_timestamp(&mut context, open_timestamp + 2 * DAY_MILLIS);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+
+        // The withdrawals are taken from the latest tiers.
+        withdraw(&mut kickstarter, &supporter_id, 15 * NEAR);
+        assert_eq!(
+            kickstarter.tier_deposits.get(&supporter_id).unwrap(),
+            vec![10 * NEAR, 5 * NEAR, 0]
+        );
+        assert_eq!(kickstarter.total_weighted_deposits, 21 * NEAR);
+
+        // A new deposit is added to the current tier, after the trimmed ones.
+        kickstarter.update_supporter_deposits(&supporter_id, &(5 * NEAR));
+        assert_eq!(
+            kickstarter.tier_deposits.get(&supporter_id).unwrap(),
+            vec![10 * NEAR, 5 * NEAR, 5 * NEAR]
+        );
+        assert_eq!(kickstarter.total_weighted_deposits, 26 * NEAR);
+
+        // A full withdraw removes the tier deposits.
+        withdraw(&mut kickstarter, &supporter_id, 20 * NEAR);
+        assert!(kickstarter.tier_deposits.get(&supporter_id).is_none());
+        assert_eq!(kickstarter.total_weighted_deposits, 0);
+    }
+
+    #[test]
+    fn test_weighted_deposit_removal_after_the_tier_expired() {
+        let (mut context, _contract, mut kickstarter) = kickstarter_only_setup();
+        set_deposit_tiers(&mut kickstarter);
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        let other_id = OTHER_SUPPORTER_ACCOUNT.to_string();
+        set_timestamp(&mut context, kickstarter.open_timestamp);
+        kickstarter.update_supporter_deposits(&supporter_id, &(10 * NEAR));
+        kickstarter.update_supporter_deposits(&other_id, &(10 * NEAR));
+
+        // After the tier expired, the withdraw removes the multiplier of the deposit tier.
+        set_timestamp(&mut context, kickstarter.open_timestamp + 3 * DAY_MILLIS);
+        withdraw(&mut kickstarter, &supporter_id, 4 * NEAR);
+        assert_eq!(kickstarter.tier_deposits.get(&supporter_id).unwrap(), vec![6 * NEAR, 0, 0]);
+        assert_eq!(kickstarter.get_weighted_deposit(&supporter_id, 6 * NEAR), 9 * NEAR);
+        assert_eq!(kickstarter.total_weighted_deposits, 24 * NEAR);
+
+        withdraw(&mut kickstarter, &supporter_id, 6 * NEAR);
+        assert!(kickstarter.tier_deposits.get(&supporter_id).is_none());
+        assert_eq!(kickstarter.total_weighted_deposits, 15 * NEAR);
+    }
+
+    fn set_keeper_bounties(kickstarter: &mut Kickstarter, keeper_bounty: Balance, max_keeper_bounties: Balance) {
+        kickstarter.katherine_fee = Some(100 * NEAR);
+        kickstarter.keeper_bounty = keeper_bounty;
+        kickstarter.max_keeper_bounties = max_keeper_bounties;
+    }
+
+    #[test]
+    fn test_keeper_bounties_reserve() {
+        let (_context, _contract, mut kickstarter) = kickstarter_only_setup();
+        set_keeper_bounties(&mut kickstarter, 20 * NEAR, 30 * NEAR);
+        let keeper_id = KEEPER_ACCOUNT.to_string();
+        let other_keeper_id = OTHER_SUPPORTER_ACCOUNT.to_string();
+
+        kickstarter.reserve_keeper_bounties();
+        assert_eq!(kickstarter.katherine_fee, Some(70 * NEAR));
+        assert_eq!(kickstarter.keeper_bounties_reserve, 30 * NEAR);
+
+        // The bounties are limited by the reserve.
+        assert_eq!(kickstarter.credit_keeper_bounty(&keeper_id), 20 * NEAR);
+        assert_eq!(kickstarter.credit_keeper_bounty(&other_keeper_id), 10 * NEAR);
+        assert_eq!(kickstarter.credit_keeper_bounty(&keeper_id), 0);
+        assert_eq!(kickstarter.get_keeper_rewards(&keeper_id), 20 * NEAR);
+        assert_eq!(kickstarter.get_keeper_rewards(&other_keeper_id), 10 * NEAR);
+        assert_eq!(kickstarter.total_keeper_bounties, 30 * NEAR);
+        assert_eq!(kickstarter.keeper_bounties_reserve, 0);
+
+        // Nothing to release, the Katherine fee does not change.
+        kickstarter.release_keeper_bounties_reserve();
+        assert_eq!(kickstarter.katherine_fee, Some(70 * NEAR));
+    }
+
+    #[test]
+    fn test_keeper_bounties_reserve_limited_by_the_fee() {
+        let (_context, _contract, mut kickstarter) = kickstarter_only_setup();
+        set_keeper_bounties(&mut kickstarter, 20 * NEAR, 300 * NEAR);
+
+        kickstarter.reserve_keeper_bounties();
+        assert_eq!(kickstarter.katherine_fee, Some(0));
+        assert_eq!(kickstarter.keeper_bounties_reserve, 100 * NEAR);
+    }
+
+    #[test]
+    fn test_release_keeper_bounties_reserve_after_the_fee_withdraw() {
+        let (_context, _contract, mut kickstarter) = kickstarter_only_setup();
+        set_keeper_bounties(&mut kickstarter, 10 * NEAR, 30 * NEAR);
+        kickstarter.reserve_keeper_bounties();
+        kickstarter.credit_keeper_bounty(&KEEPER_ACCOUNT.to_string());
+
+        // The fee collector withdraws the fee before the unfreeze.
+        kickstarter.katherine_fee_withdraw = kickstarter.get_available_katherine_fee();
+        assert_eq!(kickstarter.katherine_fee_withdraw, 70 * NEAR);
+
+        // Only the unused reserve could be withdrawn after the unfreeze.
+        kickstarter.release_keeper_bounties_reserve();
+        assert_eq!(kickstarter.keeper_bounties_reserve, 0);
+        assert_eq!(kickstarter.katherine_fee, Some(90 * NEAR));
+        assert_eq!(kickstarter.get_available_katherine_fee(), 20 * NEAR);
+        assert_eq!(kickstarter.total_keeper_bounties, 10 * NEAR);
+    }
+
+    /// Linear Kickstarter with a goal of 100 stNEAR at 1 pTOKEN and a goal of 300 stNEAR at 2 pTOKEN.
+    fn linear_kickstarter_setup(total_deposited: Balance) -> Kickstarter {
+        let (context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context, &mut contract);
+        _new_goal(&mut contract, kickstarter_id, 100 * NEAR, NEAR);
+        _new_goal(&mut contract, kickstarter_id, 300 * NEAR, 2 * NEAR);
+        contract.set_reward_mode(kickstarter_id, RewardMode::Linear);
+        let mut kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        kickstarter.total_deposited = total_deposited;
+        kickstarter
+    }
+
+    fn get_effective_tokens(kickstarter: &Kickstarter, goal_id: GoalId) -> Balance {
+        kickstarter.get_effective_goal(goal_id).unwrap().tokens_to_release_per_stnear
+    }
+
+    #[test]
+    fn test_linear_goal_below_the_first_goal() {
+        let kickstarter = linear_kickstarter_setup(50 * NEAR);
+        assert_eq!(get_effective_tokens(&kickstarter, 0), NEAR);
+        assert!(kickstarter.get_effective_terms().is_none());
+    }
+
+    #[test]
+    fn test_linear_goal_between_goals() {
+        let kickstarter = linear_kickstarter_setup(100 * NEAR);
+        assert_eq!(get_effective_tokens(&kickstarter, 0), NEAR);
+
+        let kickstarter = linear_kickstarter_setup(200 * NEAR);
+        assert_eq!(get_effective_tokens(&kickstarter, 0), 3 * NEAR / 2);
+        assert_eq!(get_effective_tokens(&kickstarter, 1), 2 * NEAR);
+        let terms = kickstarter.get_effective_terms().unwrap();
+        assert_eq!(terms.id, 0);
+        assert_eq!(terms.tokens_to_release_per_stnear, 3 * NEAR / 2);
+        assert_eq!(terms.unfreeze_timestamp, to_millis(UNFREEZE_TIME_IN_DAYS));
+    }
+
+    #[test]
+    fn test_linear_goal_above_the_last_goal() {
+        let kickstarter = linear_kickstarter_setup(400 * NEAR);
+        // The next goal is achieved, so the first goal keeps its own terms.
+        assert_eq!(get_effective_tokens(&kickstarter, 0), NEAR);
+        assert_eq!(get_effective_tokens(&kickstarter, 1), 2 * NEAR);
+        let terms = kickstarter.get_effective_terms().unwrap();
+        assert_eq!(terms.id, 1);
+        assert_eq!(terms.tokens_to_release_per_stnear, 2 * NEAR);
     }
 }
@@ -1,7 +1,7 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{UnorderedMap, UnorderedSet, Vector};
 use near_sdk::json_types::ValidAccountId;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseResult};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance, Gas, PanicOnDefault, PromiseOrValue, PromiseResult};
 use std::convert::TryInto;
 
 mod claim;
@@ -9,25 +9,34 @@ mod constants;
 mod deposit;
 mod interest;
 mod internal;
+mod migration;
+mod roles;
+mod storage;
 mod types;
+mod validation;
 mod withdraw;
 
 pub mod goal;
 pub mod interface;
 pub mod kickstarter;
+pub mod metadata;
 pub mod supporter;
 pub mod utils;
+pub mod vesting;
 pub use crate::utils::*;
 
-use crate::{constants::*, goal::*, kickstarter::*, supporter::*, types::*};
+use crate::{constants::*, goal::*, kickstarter::*, metadata::*, supporter::*, types::*, vesting::*};
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct KatherineFundraising {
     pub owner_id: AccountId,
+    // Nominee for the ownership, it must accept it to become the new owner.
+    pub pending_owner_id: Option<AccountId>,
     pub supporters: UnorderedMap<SupporterId, Supporter>,
-    pub kickstarters: Vector<Kickstarter>,
+    pub kickstarters: Vector<VersionedKickstarter>,
     pub kickstarter_id_by_slug: UnorderedMap<String, KickstarterId>,
+    pub kickstarter_metadata: UnorderedMap<KickstarterId, KickstarterMetadata>,
 
     /// Min amount accepted for supporters
     pub min_deposit_amount: Balance,
@@ -40,6 +49,21 @@ pub struct KatherineFundraising {
 
     // Active kickstarter projects.
     pub active_projects: UnorderedSet<KickstarterId>,
+
+    // Accounts with a granted role. The owner_id is the super-admin.
+    pub operators: UnorderedSet<AccountId>,
+    pub fee_collectors: UnorderedSet<AccountId>,
+    pub robots: UnorderedSet<AccountId>,
+
+    // Emergency stop for all the Kickstarters.
+    pub deposits_paused: bool,
+    pub outflows_paused: bool,
+
+    // NEP-145 storage deposits of the Supporters.
+    pub storage_deposits: UnorderedMap<AccountId, Balance>,
+
+    // Unfreezed Kickstarters with stNEAR interest not withdrawn yet.
+    pub kickstarters_with_pending_interest: u32,
 }
 
 #[near_bindgen]
@@ -52,16 +76,26 @@ impl KatherineFundraising {
         katherine_fee_percent: BasisPoints,
     ) -> Self {
         assert!(!env::state_exists(), "The contract is already initialized");
+        Self::assert_katherine_fee_percent(katherine_fee_percent);
         Self {
             owner_id,
+            pending_owner_id: None,
             supporters: UnorderedMap::new(Keys::Supporters),
             kickstarters: Vector::new(Keys::Kickstarters),
             kickstarter_id_by_slug: UnorderedMap::new(Keys::KickstarterId),
+            kickstarter_metadata: UnorderedMap::new(Keys::KickstarterMetadata),
             min_deposit_amount: Balance::from(min_deposit_amount),
             metapool_contract_address,
             katherine_fee_percent,
-            max_goals_per_kickstarter: 5,
+            max_goals_per_kickstarter: DEFAULT_MAX_GOALS_PER_KICKSTARTER,
             active_projects: UnorderedSet::new(Keys::Active),
+            operators: UnorderedSet::new(Keys::Operators),
+            fee_collectors: UnorderedSet::new(Keys::FeeCollectors),
+            robots: UnorderedSet::new(Keys::Robots),
+            deposits_paused: false,
+            outflows_paused: false,
+            storage_deposits: UnorderedMap::new(Keys::StorageDeposits),
+            kickstarters_with_pending_interest: 0,
         }
     }
 
@@ -100,19 +134,20 @@ impl KatherineFundraising {
 
     pub fn process_kickstarter(&mut self, kickstarter_id: KickstarterIdJSON) {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_allowed_keeper(&kickstarter);
         if kickstarter.successful.is_none() {
             if kickstarter.close_timestamp <= get_current_epoch_millis() {
                 match kickstarter.get_achieved_goal() {
                     Some(goal) => {
-                        self.activate_successful_kickstarter(kickstarter_id, goal.id);
+                        self.activate_successful_kickstarter(
+                            kickstarter_id,
+                            goal.id,
+                            env::predecessor_account_id()
+                        );
                         log!("kickstarter was successfully activated");
                     }
                     None => {
-                        kickstarter.active = false;
-                        self.active_projects.remove(&kickstarter.id);
-                        kickstarter.successful = Some(false);
-                        self.kickstarters
-                            .replace(kickstarter_id as u64, &kickstarter);
+                        self.internal_deactivate_unsuccessful_kickstarter(&mut kickstarter);
                         log!("kickstarter successfully deactivated");
                     }
                 }
@@ -124,6 +159,23 @@ impl KatherineFundraising {
         }
     }
 
+    /// Processes many Kickstarters with a single stNEAR price. The result of each Kickstarter
+    /// is reported instead of failing the whole batch.
+    pub fn process_kickstarters(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+    ) -> PromiseOrValue<Vec<BatchResultJSON>> {
+        let min_prepaid_gas = 2 * GAS_FOR_GET_STNEAR
+            + GAS_FOR_BATCH_ITEM * kickstarter_ids.len() as Gas
+            + FIVE_TGAS;
+        assert!(
+            env::prepaid_gas() > min_prepaid_gas,
+            "gas required {}",
+            min_prepaid_gas
+        );
+        self.internal_process_kickstarters(kickstarter_ids)
+    }
+
     /// Returns kickstarters ids ready to unfreeze.
     pub fn get_kickstarters_to_unfreeze(
         &self,
@@ -151,9 +203,10 @@ impl KatherineFundraising {
     /// Start the cross-contract call to unfreeze the kickstarter funds.
     pub fn unfreeze_kickstarter_funds(&mut self, kickstarter_id: KickstarterIdJSON) {
         let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_allowed_keeper(&kickstarter);
         if kickstarter.successful == Some(true) && kickstarter.stnear_price_at_unfreeze == None {
             kickstarter.assert_funds_can_be_unfreezed();
-            self.internal_unfreeze_kickstarter_funds(kickstarter_id);
+            self.internal_unfreeze_kickstarter_funds(kickstarter_id, env::predecessor_account_id());
             log!(
                 "UNFREEZE: funds successfully unfreezed for Kickstarter {}",
                 kickstarter_id
@@ -161,6 +214,21 @@ impl KatherineFundraising {
         }
     }
 
+    pub fn unfreeze_kickstarters(
+        &mut self,
+        kickstarter_ids: Vec<KickstarterIdJSON>,
+    ) -> PromiseOrValue<Vec<BatchResultJSON>> {
+        let min_prepaid_gas = 2 * GAS_FOR_GET_STNEAR
+            + GAS_FOR_BATCH_ITEM * kickstarter_ids.len() as Gas
+            + FIVE_TGAS;
+        assert!(
+            env::prepaid_gas() > min_prepaid_gas,
+            "gas required {}",
+            min_prepaid_gas
+        );
+        self.internal_unfreeze_kickstarters(kickstarter_ids)
+    }
+
     /*****************************/
     /*   Supporters functions    */
     /*****************************/
@@ -168,7 +236,7 @@ impl KatherineFundraising {
     pub fn withdraw_all(&mut self, kickstarter_id: KickstarterIdJSON) {
         let supporter_id = env::predecessor_account_id().clone().try_into().unwrap();
         let kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        if !kickstarter.is_within_funding_period() {
+        if kickstarter.successful == Some(true) {
             kickstarter.assert_funds_must_be_unfreezed();
         }
         let amount = self.get_supporter_total_deposit_in_kickstarter(supporter_id, kickstarter_id, None);
@@ -189,6 +257,7 @@ impl KatherineFundraising {
             amount > 0,
             "The amount to withdraw should be greater than Zero!"
         );
+        self.assert_outflows_not_paused(&kickstarter);
         let supporter_id: SupporterId = env::predecessor_account_id();
         match kickstarter.successful {
             Some(true) => {
@@ -220,6 +289,43 @@ impl KatherineFundraising {
         };
     }
 
+    /// Moves a deposit to another Kickstarter during the funding period, without transferring the stNEAR.
+    /// Returns the moved amount, the whole deposit if the min deposit amount would not remain.
+    pub fn move_deposit(
+        &mut self,
+        from_kickstarter_id: KickstarterIdJSON,
+        to_kickstarter_id: KickstarterIdJSON,
+        amount: BalanceJSON,
+    ) -> BalanceJSON {
+        assert!(
+            from_kickstarter_id != to_kickstarter_id,
+            "The deposit must be moved to another Kickstarter!"
+        );
+        let amount = Balance::from(amount);
+        assert!(
+            amount > 0,
+            "The amount to move should be greater than Zero!"
+        );
+        let mut from_kickstarter = self.internal_get_kickstarter(from_kickstarter_id);
+        let mut to_kickstarter = self.internal_get_kickstarter(to_kickstarter_id);
+        self.assert_outflows_not_paused(&from_kickstarter);
+        match from_kickstarter.successful {
+            Some(true) => panic!("The deposits of a successful Kickstarter cannot be moved!"),
+            Some(false) => (),
+            None => assert!(
+                get_current_epoch_millis() < from_kickstarter.close_timestamp,
+                "The funding period is over, Kickstarter must be evaluated!"
+            ),
+        }
+        let moved = self.internal_move_deposit(
+            amount,
+            &mut from_kickstarter,
+            &mut to_kickstarter,
+            env::predecessor_account_id(),
+        );
+        BalanceJSON::from(moved)
+    }
+
     pub fn claim_all_kickstarter_tokens(&mut self, kickstarter_id: KickstarterIdJSON) {
         let account_id = env::predecessor_account_id();
         let available_rewards = self.get_supporter_available_rewards(
@@ -241,9 +347,26 @@ impl KatherineFundraising {
     ) {
         let account_id = env::predecessor_account_id();
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_outflows_not_paused(&kickstarter);
         self.internal_claim_kickstarter_tokens(amount, &mut kickstarter, account_id);
     }
 
+    /// Claims all the available referral rewards of the predecessor.
+    pub fn claim_referral_rewards(&mut self, kickstarter_id: KickstarterIdJSON) {
+        let referrer_id = env::predecessor_account_id();
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_outflows_not_paused(&kickstarter);
+        self.internal_claim_referral_rewards(&mut kickstarter, referrer_id);
+    }
+
+    /// Claims the keeper bounties credited to the predecessor.
+    pub fn claim_keeper_rewards(&mut self, kickstarter_id: KickstarterIdJSON) {
+        let keeper_id = env::predecessor_account_id();
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_outflows_not_paused(&kickstarter);
+        self.internal_claim_keeper_rewards(&mut kickstarter, keeper_id);
+    }
+
     /*****************************/
     /*   Kickstarter functions   */
     /*****************************/
@@ -254,6 +377,7 @@ impl KatherineFundraising {
     ) {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
         kickstarter.assert_kickstarter_owner();
+        self.assert_outflows_not_paused(&kickstarter);
         assert_eq!(
             kickstarter.successful,
             Some(true),
@@ -269,19 +393,45 @@ impl KatherineFundraising {
         }
     }
 
+    /// Refunds the excedent to all the reward funders, pro rata. Only for the owner or a funder.
     pub fn kickstarter_withdraw_excedent(&mut self, kickstarter_id: KickstarterIdJSON) {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        kickstarter.assert_kickstarter_owner();
+        let account_id = env::predecessor_account_id();
         assert!(
-            kickstarter.close_timestamp < get_current_epoch_millis(),
+            kickstarter.owner_id == account_id || kickstarter.reward_funders.get(&account_id).is_some(),
+            "Only allowed for Kickstarter owner or reward funders."
+        );
+        let min_prepaid_gas = (GAS_FOR_FT_TRANSFER + GAS_FOR_RESOLVE_TRANSFER)
+            * kickstarter.reward_funders.len()
+            + FIVE_TGAS;
+        assert!(
+            env::prepaid_gas() > min_prepaid_gas,
+            "gas required {}",
+            min_prepaid_gas
+        );
+        self.assert_outflows_not_paused(&kickstarter);
+        assert!(
+            kickstarter.is_cancelled() || kickstarter.close_timestamp < get_current_epoch_millis(),
             "The excedent is avalable only after the funding period ends"
         );
 
         let excedent: Balance = match kickstarter.successful {
             Some(true) => {
+                // The totals include the withdrawn fee and the claimed rewards, the available
+                // reward tokens are only reduced by the excedent refunds.
                 let katherine_fee = kickstarter.katherine_fee.unwrap();
                 let total_tokens_to_release = kickstarter.total_tokens_to_release.unwrap();
-                kickstarter.available_reward_tokens - (katherine_fee + total_tokens_to_release)
+                let referral_rewards = self.calculate_total_referral_rewards(
+                    &kickstarter,
+                    kickstarter.get_winner_goal().tokens_to_release_per_stnear
+                );
+                kickstarter.available_reward_tokens.saturating_sub(
+                    katherine_fee
+                        + total_tokens_to_release
+                        + referral_rewards
+                        + kickstarter.total_keeper_bounties
+                        + kickstarter.keeper_bounties_reserve
+                )
             }
             Some(false) => {
                 log!("Returning all available reward tokens!");
@@ -305,14 +455,15 @@ impl KatherineFundraising {
 
     /// Withdraws the Katherine Fee from a Kickstarter.
     pub fn withdraw_katherine_fee(&mut self, kickstarter_id: KickstarterIdJSON) {
-        self.assert_only_admin();
+        self.assert_only_fee_collector();
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_outflows_not_paused(&kickstarter);
         assert!(
             kickstarter.close_timestamp < get_current_epoch_millis(),
             "To withdraw the Katherine Fee the Kickstarter must be closed."
         );
         let katherine_fee: Balance = if kickstarter.successful == Some(true) {
-            kickstarter.katherine_fee.unwrap().into()
+            kickstarter.get_available_katherine_fee()
         } else {
             panic!("Kickstarter was unsuccessful.");
         };
@@ -337,8 +488,7 @@ impl KatherineFundraising {
         max_tokens_to_release_per_stnear: BalanceJSON,
         token_contract_decimals: u8,
     ) -> KickstarterIdJSON {
-        //ONLY ADMINS CAN CREATE KICKSTARTERS? YES
-        self.assert_only_admin();
+        self.assert_only_operator();
         self.assert_unique_slug(&slug);
         let id = self.kickstarters.len() as KickstarterId;
         self.internal_create_kickstarter(
@@ -356,31 +506,53 @@ impl KatherineFundraising {
     }
 
     pub fn delete_kickstarter(&mut self, id: KickstarterIdJSON) {
-        panic!("Kickstarter {} must not be deleted!", id);
+        panic!("Kickstarter {} must not be deleted! Use cancel_kickstarter instead.", id);
+    }
+
+    /// Cancels a Kickstarter before or during the funding period. Supporters could withdraw
+    /// all their stNEAR and the Kickstarter could withdraw all the pTOKEN as excedent.
+    pub fn cancel_kickstarter(&mut self, kickstarter_id: KickstarterIdJSON, reason: String) {
+        self.assert_only_admin();
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        kickstarter.assert_not_cancelled();
+        assert!(
+            kickstarter.successful.is_none(),
+            "Kickstarter was already evaluated!"
+        );
+        assert!(
+            get_current_epoch_millis() < kickstarter.close_timestamp,
+            "The funding period is over, Kickstarter must be evaluated!"
+        );
+        kickstarter.active = false;
+        kickstarter.successful = Some(false);
+        kickstarter.cancel_reason = Some(reason);
+        self.active_projects.remove(&kickstarter.id);
+        self.internal_save_kickstarter(&kickstarter);
+        log!(
+            "CANCEL: KickstarterId {} cancelled. Reason: {}",
+            kickstarter_id,
+            kickstarter.cancel_reason.as_ref().unwrap()
+        );
     }
 
+    /// Only the sent fields are updated. Goals, deposits and reward tokens are kept.
     pub fn update_kickstarter(
         &mut self,
         id: KickstarterIdJSON,
-        name: String,
-        slug: String,
-        owner_id: AccountId,
-        open_timestamp: EpochMillis,
-        close_timestamp: EpochMillis,
-        token_contract_address: AccountId,
-        deposits_hard_cap: BalanceJSON,
-        max_tokens_to_release_per_stnear: BalanceJSON,
-        token_contract_decimals: u8,
+        name: Option<String>,
+        slug: Option<String>,
+        owner_id: Option<AccountId>,
+        open_timestamp: Option<EpochMillis>,
+        close_timestamp: Option<EpochMillis>,
+        token_contract_address: Option<AccountId>,
+        deposits_hard_cap: Option<BalanceJSON>,
+        max_tokens_to_release_per_stnear: Option<BalanceJSON>,
+        token_contract_decimals: Option<u8>,
     ) {
-        let old_kickstarter = self.internal_get_kickstarter(id);
-        let goal_creator_id = env::predecessor_account_id();
-        assert!(
-            self.owner_id == goal_creator_id || old_kickstarter.owner_id == goal_creator_id,
-            "A Kickstarter could only be updated by admin, or the Kickstarter owner."
-        );
-        self.assert_unique_slug(&slug);
+        let mut kickstarter = self.internal_get_kickstarter(id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
         self.internal_update_kickstarter(
-            old_kickstarter,
+            &mut kickstarter,
             name,
             slug,
             owner_id,
@@ -393,16 +565,251 @@ impl KatherineFundraising {
         );
     }
 
+    /// Send `null` to remove the metadata.
+    pub fn set_kickstarter_metadata(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        metadata: Option<KickstarterMetadata>,
+    ) {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        match metadata {
+            Some(metadata) => {
+                metadata.assert_valid();
+                self.kickstarter_metadata.insert(&kickstarter.id, &metadata);
+            }
+            None => {
+                self.kickstarter_metadata.remove(&kickstarter.id);
+            }
+        }
+        log!("METADATA: KickstarterId {} metadata updated", kickstarter_id);
+    }
+
     pub fn change_kickstarter_owner(
         &mut self,
         kickstarter_id: KickstarterIdJSON,
         new_owner_id: ValidAccountId
     ) {
-        self.assert_only_admin();
+        self.assert_only_operator();
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
         kickstarter.owner_id = new_owner_id.to_string();
-        self.kickstarters
-            .replace(kickstarter.id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
+    }
+
+    pub fn set_min_deposit_amount(&mut self, min_deposit_amount: BalanceJSON) {
+        self.assert_only_admin();
+        let min_deposit_amount = Balance::from(min_deposit_amount);
+        assert!(
+            min_deposit_amount <= MAX_MIN_DEPOSIT_AMOUNT,
+            "Min deposit amount cannot exceed {}",
+            MAX_MIN_DEPOSIT_AMOUNT
+        );
+        log!(
+            "PARAMS: min_deposit_amount changed from {} to {}",
+            self.min_deposit_amount,
+            min_deposit_amount
+        );
+        self.min_deposit_amount = min_deposit_amount;
+    }
+
+    /// The new fee only applies to the Kickstarters created after the change.
+    pub fn set_katherine_fee_percent(&mut self, katherine_fee_percent: BasisPoints) {
+        self.assert_only_admin();
+        Self::assert_katherine_fee_percent(katherine_fee_percent);
+        log!(
+            "PARAMS: katherine_fee_percent changed from {} to {}",
+            self.katherine_fee_percent,
+            katherine_fee_percent
+        );
+        self.katherine_fee_percent = katherine_fee_percent;
+    }
+
+    pub fn set_max_goals_per_kickstarter(&mut self, max_goals_per_kickstarter: u8) {
+        self.assert_only_admin();
+        assert!(
+            max_goals_per_kickstarter > 0 && max_goals_per_kickstarter <= MAX_GOALS_PER_KICKSTARTER,
+            "Max goals per kickstarter must be between 1 and {}",
+            MAX_GOALS_PER_KICKSTARTER
+        );
+        log!(
+            "PARAMS: max_goals_per_kickstarter changed from {} to {}",
+            self.max_goals_per_kickstarter,
+            max_goals_per_kickstarter
+        );
+        self.max_goals_per_kickstarter = max_goals_per_kickstarter;
+    }
+
+    /// The stNEAR of the Kickstarters is held in the current Meta Pool contract, so it could only
+    /// be changed when no Kickstarter is active and all the stNEAR was withdrawn.
+    pub fn set_metapool_contract_address(&mut self, metapool_contract_address: ValidAccountId) {
+        self.assert_only_admin();
+        self.assert_no_stnear_held();
+        let metapool_contract_address: AccountId = metapool_contract_address.into();
+        log!(
+            "PARAMS: metapool_contract_address changed from {} to {}",
+            self.metapool_contract_address,
+            metapool_contract_address
+        );
+        self.metapool_contract_address = metapool_contract_address;
+    }
+
+    /// Emergency stop for all the Kickstarters. Operators could pause, only the admin could unpause.
+    pub fn set_paused(&mut self, deposits_paused: bool, outflows_paused: bool) {
+        self.assert_pause_permission(
+            (self.deposits_paused && !deposits_paused) || (self.outflows_paused && !outflows_paused)
+        );
+        self.deposits_paused = deposits_paused;
+        self.outflows_paused = outflows_paused;
+        log!(
+            "PAUSE: deposits paused {}, outflows paused {}",
+            deposits_paused,
+            outflows_paused
+        );
+    }
+
+    /// Emergency stop for a single Kickstarter. Operators could pause, only the admin could unpause.
+    pub fn set_kickstarter_paused(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        deposits_paused: bool,
+        outflows_paused: bool,
+    ) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_pause_permission(
+            (kickstarter.deposits_paused && !deposits_paused)
+                || (kickstarter.outflows_paused && !outflows_paused)
+        );
+        kickstarter.deposits_paused = deposits_paused;
+        kickstarter.outflows_paused = outflows_paused;
+        self.internal_save_kickstarter(&kickstarter);
+        log!(
+            "PAUSE: KickstarterId {} deposits paused {}, outflows paused {}",
+            kickstarter_id,
+            deposits_paused,
+            outflows_paused
+        );
+    }
+
+    /// The referral fee could only be changed before the funding period.
+    pub fn set_kickstarter_referral_fee(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        referral_fee_percent: BasisPoints,
+    ) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        kickstarter.assert_not_cancelled();
+        kickstarter.assert_before_funding_period();
+        assert!(
+            referral_fee_percent <= MAX_REFERRAL_FEE_PERCENT,
+            "Referral fee cannot exceed {} basis points",
+            MAX_REFERRAL_FEE_PERCENT
+        );
+        kickstarter.referral_fee_percent = referral_fee_percent;
+        kickstarter.enough_reward_tokens = {
+            kickstarter.available_reward_tokens >= self.calculate_min_tokens_to_allow_support(&kickstarter)
+        };
+        self.internal_save_kickstarter(&kickstarter);
+        log!(
+            "REFERRAL: KickstarterId {} referral fee set to {} basis points",
+            kickstarter_id,
+            referral_fee_percent
+        );
+    }
+
+    /// Early-bird tiers, sorted by end timestamp. Send an empty list to remove them.
+    pub fn set_deposit_tiers(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        deposit_tiers: Vec<DepositTier>,
+    ) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        kickstarter.assert_not_cancelled();
+        kickstarter.assert_before_funding_period();
+        assert!(
+            deposit_tiers.is_empty() || !kickstarter.time_weighted,
+            "Deposit tiers are not compatible with the time-weighted deposits"
+        );
+        kickstarter.deposit_tiers = deposit_tiers;
+        self.assert_valid_config(&kickstarter.to_config());
+        kickstarter.enough_reward_tokens = {
+            kickstarter.available_reward_tokens >= self.calculate_min_tokens_to_allow_support(&kickstarter)
+        };
+        self.internal_save_kickstarter(&kickstarter);
+        log!(
+            "TIERS: KickstarterId {} has {} deposit tiers",
+            kickstarter_id,
+            kickstarter.deposit_tiers.len()
+        );
+    }
+
+    /// Rewards split by the time the deposits were held during the funding period.
+    pub fn set_time_weighted_deposits(&mut self, kickstarter_id: KickstarterIdJSON, time_weighted: bool) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        kickstarter.assert_not_cancelled();
+        kickstarter.assert_before_funding_period();
+        assert!(
+            !time_weighted || kickstarter.deposit_tiers.is_empty(),
+            "Deposit tiers are not compatible with the time-weighted deposits"
+        );
+        kickstarter.time_weighted = time_weighted;
+        self.internal_save_kickstarter(&kickstarter);
+        log!("REWARDS: KickstarterId {} time-weighted deposits {}", kickstarter_id, time_weighted);
+    }
+
+    pub fn set_reward_mode(&mut self, kickstarter_id: KickstarterIdJSON, reward_mode: RewardMode) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        kickstarter.assert_not_cancelled();
+        kickstarter.assert_before_funding_period();
+        kickstarter.reward_mode = reward_mode;
+        self.internal_save_kickstarter(&kickstarter);
+        log!("REWARDS: KickstarterId {} reward mode is {:?}", kickstarter_id, reward_mode);
+    }
+
+    /// The keeper bounty is paid from the Katherine fee, in pTOKEN with 24 decimals, for each
+    /// transition. The max keeper bounties is the cap for all the transitions of the Kickstarter,
+    /// reserved from the fee at the activation, so it could only be set before the evaluation.
+    pub fn set_keeper_bounty(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        keeper_bounty: BalanceJSON,
+        max_keeper_bounties: BalanceJSON,
+    ) {
+        self.assert_only_operator();
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        assert!(
+            kickstarter.successful.is_none(),
+            "The keeper bounty cannot be changed after the evaluation!"
+        );
+        kickstarter.keeper_bounty = Balance::from(keeper_bounty);
+        kickstarter.max_keeper_bounties = Balance::from(max_keeper_bounties);
+        self.internal_save_kickstarter(&kickstarter);
+        log!(
+            "KEEPER: KickstarterId {} bounty is {} pTOKEN, max {} pTOKEN",
+            kickstarter_id,
+            kickstarter.keeper_bounty,
+            kickstarter.max_keeper_bounties
+        );
+    }
+
+    /// Allows another account to fund the rewards, the owner is always allowed.
+    pub fn allow_reward_funder(&mut self, kickstarter_id: KickstarterIdJSON, funder_id: ValidAccountId) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        kickstarter.funders_allowlist.insert(funder_id.as_ref());
+        self.internal_save_kickstarter(&kickstarter);
+        log!("FUNDERS: {} allowed for KickstarterId {}", funder_id.as_ref(), kickstarter_id);
+    }
+
+    pub fn disallow_reward_funder(&mut self, kickstarter_id: KickstarterIdJSON, funder_id: ValidAccountId) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        kickstarter.funders_allowlist.remove(funder_id.as_ref());
+        self.internal_save_kickstarter(&kickstarter);
+        log!("FUNDERS: {} disallowed for KickstarterId {}", funder_id.as_ref(), kickstarter_id);
     }
 
     pub fn create_goal(
@@ -414,13 +821,12 @@ impl KatherineFundraising {
         tokens_to_release_per_stnear: BalanceJSON,
         cliff_timestamp: EpochMillis,
         end_timestamp: EpochMillis,
+        min_supporters: Option<u32>,
+        max_supporter_share: Option<BasisPoints>,
+        vesting_schedule: Option<VestingSchedule>,
     ) -> GoalId {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        let goal_creator_id = env::predecessor_account_id();
-        assert!(
-            self.owner_id == goal_creator_id || kickstarter.owner_id == goal_creator_id,
-            "A goal could only be created by admin, or the Kickstarter owner."
-        );
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
         self.internal_create_goal(
             &mut kickstarter,
             name,
@@ -429,19 +835,41 @@ impl KatherineFundraising {
             tokens_to_release_per_stnear,
             cliff_timestamp,
             end_timestamp,
+            min_supporters,
+            max_supporter_share,
+            vesting_schedule,
         )
     }
 
+    pub fn set_goals(&mut self, kickstarter_id: KickstarterIdJSON, goals: Vec<GoalInput>) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        self.internal_set_goals(&mut kickstarter, goals);
+        log!(
+            "GOALS: KickstarterId {} has {} goals",
+            kickstarter_id,
+            kickstarter.get_number_of_goals()
+        );
+    }
+
+    pub fn update_goal(
+        &mut self,
+        kickstarter_id: KickstarterIdJSON,
+        goal_id: GoalIdJSON,
+        goal: GoalInput,
+    ) {
+        let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
+        self.internal_update_goal(&mut kickstarter, goal_id, goal);
+        log!("GOALS: GoalId {} updated for KickstarterId {}", goal_id, kickstarter_id);
+    }
+
     pub fn delete_last_goal(
         &mut self,
         kickstarter_id: KickstarterIdJSON
     ) {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        let goal_creator_id = env::predecessor_account_id();
-        assert!(
-            self.owner_id == goal_creator_id || kickstarter.owner_id == goal_creator_id,
-            "A goal could only be created by admin, or the Kickstarter owner."
-        );
+        self.assert_operator_or_kickstarter_owner(&kickstarter);
         self.internal_delete_last_goal(&mut kickstarter);
     }
 
@@ -460,12 +888,7 @@ impl KatherineFundraising {
         match self.supporters.get(&supporter_id) {
             Some(supporter) => {
                 if supporter.is_supporting(kickstarter.id) && kickstarter.winner_goal_id.is_some() {
-                    let goal = kickstarter.get_winner_goal();
-                    let rewards = self.internal_get_supporter_rewards(
-                        &supporter_id,
-                        &kickstarter,
-                        goal.tokens_to_release_per_stnear,
-                    );
+                    let rewards = self.internal_get_supporter_rewards(&supporter_id, &kickstarter);
                     return Some(BalanceJSON::from(rewards));
                 } else {
                     return None;
@@ -496,6 +919,122 @@ impl KatherineFundraising {
         }
     }
 
+    /// Unlock timeline of the Supporter rewards, following the winner goal vesting schedule.
+    pub fn get_vesting_schedule(
+        &self,
+        supporter_id: SupporterIdJSON,
+        kickstarter_id: KickstarterIdJSON,
+    ) -> Option<Vec<VestingUnlockJSON>> {
+        let supporter_id = SupporterId::from(supporter_id);
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        match self.supporters.get(&supporter_id) {
+            Some(supporter) => {
+                if supporter.is_supporting(kickstarter.id) && kickstarter.winner_goal_id.is_some() {
+                    return Some(self.internal_get_vesting_schedule(&supporter_id, &kickstarter));
+                } else {
+                    return None;
+                }
+            }
+            None => return None,
+        }
+    }
+
+    /// stNEAR deposited by the Supporters referred by the referrer.
+    pub fn get_referred_deposits(
+        &self,
+        referrer_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+    ) -> BalanceJSON {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        BalanceJSON::from(kickstarter.get_referred_deposits(referrer_id.as_ref()))
+    }
+
+    pub fn get_referrer_of(
+        &self,
+        supporter_id: SupporterIdJSON,
+        kickstarter_id: KickstarterIdJSON,
+    ) -> Option<AccountId> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        kickstarter.referrers.get(supporter_id.as_ref())
+    }
+
+    /// Get the total referral rewards that the referrer could claim regardless of the current timestamp.
+    pub fn get_referrer_total_rewards(
+        &self,
+        referrer_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+    ) -> Option<BalanceJSON> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        if kickstarter.successful == Some(true) {
+            let rewards = self.internal_get_referrer_rewards(referrer_id.as_ref(), &kickstarter);
+            Some(BalanceJSON::from(rewards))
+        } else {
+            None
+        }
+    }
+
+    /// Available referral rewards that the referrer could currently claim.
+    pub fn get_referrer_available_rewards(
+        &self,
+        referrer_id: ValidAccountId,
+        kickstarter_id: KickstarterIdJSON,
+    ) -> Option<BalanceJSON> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        if kickstarter.successful == Some(true) {
+            let rewards = self.internal_get_available_referral_rewards(referrer_id.as_ref(), &kickstarter);
+            Some(BalanceJSON::from(rewards))
+        } else {
+            None
+        }
+    }
+
+    /// Keeper bounties that the account could claim, with 24 decimals.
+    pub fn get_keeper_rewards(&self, keeper_id: ValidAccountId, kickstarter_id: KickstarterIdJSON) -> BalanceJSON {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        BalanceJSON::from(kickstarter.get_keeper_rewards(keeper_id.as_ref()))
+    }
+
+    /// Reward tokens deposited by each funder, with 24 decimals.
+    pub fn get_reward_funders(&self, kickstarter_id: KickstarterIdJSON) -> Vec<RewardFunderJSON> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        kickstarter
+            .reward_funders
+            .iter()
+            .map(|(funder_id, amount)| RewardFunderJSON {
+                funder_id,
+                amount: BalanceJSON::from(amount),
+            })
+            .collect()
+    }
+
+    pub fn get_reward_funders_allowlist(&self, kickstarter_id: KickstarterIdJSON) -> Vec<AccountId> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        kickstarter.funders_allowlist.to_vec()
+    }
+
+    /// Audit of the deposits made by an account for another Supporter.
+    pub fn get_deposits_on_behalf(
+        &self,
+        kickstarter_id: KickstarterIdJSON,
+        from_index: u32,
+        limit: u32,
+    ) -> Vec<DepositOnBehalfJSON> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        let keys = kickstarter.deposits_on_behalf.keys_as_vector();
+        let values = kickstarter.deposits_on_behalf.values_as_vector();
+        let start: u64 = from_index.into();
+        let mut result: Vec<DepositOnBehalfJSON> = Vec::new();
+        for index in start..std::cmp::min(start + limit as u64, keys.len()) {
+            let (depositor_id, beneficiary_id) = keys.get(index).unwrap();
+            result.push(DepositOnBehalfJSON {
+                depositor_id,
+                beneficiary_id,
+                amount: BalanceJSON::from(values.get(index).unwrap()),
+            });
+        }
+        result
+    }
+
     /// Available rewards that the Supporter could currently claim.
     pub fn get_admin_fee_rewards(
         &self,
@@ -503,12 +1042,28 @@ impl KatherineFundraising {
     ) -> BalanceJSON {
         let kickstarter = self.internal_get_kickstarter(kickstarter_id);
         if kickstarter.successful == Some(true) {
-            kickstarter.katherine_fee.unwrap().into()
+            kickstarter.get_available_katherine_fee().into()
         } else {
             panic!("Kickstarter was unsuccessful.");
         }
     }
 
+    pub fn get_contract_params(&self) -> ContractParamsJSON {
+        ContractParamsJSON {
+            min_deposit_amount: BalanceJSON::from(self.min_deposit_amount),
+            metapool_contract_address: self.metapool_contract_address.clone(),
+            katherine_fee_percent: self.katherine_fee_percent,
+            max_goals_per_kickstarter: self.max_goals_per_kickstarter,
+        }
+    }
+
+    pub fn get_paused_status(&self) -> PausedStatusJSON {
+        PausedStatusJSON {
+            deposits_paused: self.deposits_paused,
+            outflows_paused: self.outflows_paused,
+        }
+    }
+
     pub fn get_active_projects(
         &self,
         from_index: u32,
@@ -536,7 +1091,15 @@ impl KatherineFundraising {
 
     pub fn get_project_details(&self, kickstarter_id: KickstarterIdJSON) -> KickstarterDetailsJSON {
         let kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        kickstarter.to_details_json()
+        kickstarter.to_details_json(self.kickstarter_metadata.get(&kickstarter.id))
+    }
+
+    pub fn get_kickstarter_metadata(
+        &self,
+        kickstarter_id: KickstarterIdJSON,
+    ) -> Option<KickstarterMetadata> {
+        let kickstarter = self.internal_get_kickstarter(kickstarter_id);
+        self.kickstarter_metadata.get(&kickstarter.id)
     }
 
     pub fn get_kickstarters(&self, from_index: usize, limit: usize) -> Vec<KickstarterJSON> {
@@ -699,39 +1262,14 @@ impl KatherineFundraising {
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use near_sdk::{testing_env, MockedBlockchain, VMContext};
-    mod unit_test_utils;
+    use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::json_types::U128;
+    use near_sdk::VMContext;
+    pub(crate) mod unit_test_utils;
     use super::*;
     use unit_test_utils::*;
 
-    /// Get initial context for tests
-    fn basic_context() -> VMContext {
-        get_context(
-            SYSTEM_ACCOUNT.into(),
-            ntoy(TEST_INITIAL_BALANCE),
-            0,
-            to_ts(START_TIME_IN_DAYS),
-            false,
-        )
-    }
-
-    /// Creates a new contract
-    fn new_contract() -> KatherineFundraising {
-        KatherineFundraising::new(
-            OWNER_ACCOUNT.into(),
-            2,
-            METAPOOL_CONTRACT_ADDRESS.to_string(),
-            2,
-        )
-    }
-
-    fn contract_only_setup() -> (VMContext, KatherineFundraising) {
-        let context = basic_context();
-        testing_env!(context.clone());
-        let contract = new_contract();
-        return (context, contract);
-    }
-
     #[test]
     fn test_create_kickstarter() {
         let (_context, mut contract) = contract_only_setup();
@@ -741,8 +1279,8 @@ mod tests {
 
     #[test]
     fn test_get_kickstarters() {
-        let (_context, mut contract) = contract_only_setup();
-        contract.get_kickstarters(0, 49);
+        let (_context, contract) = contract_only_setup();
+        assert!(contract.get_kickstarters(0, 49).is_empty());
     }
 
     #[test]
@@ -750,27 +1288,847 @@ mod tests {
         let (_context, mut contract) = contract_only_setup();
         _new_kickstarter(_context, &mut contract);
         let kickstarter_id = contract.kickstarters.len() - 1;
-        let mut k = contract.kickstarters.get(kickstarter_id).unwrap();
+        let mut k = contract.internal_get_kickstarter(kickstarter_id as KickstarterId);
         k.update_supporter_deposits(&String::from(SUPPORTER_ACCOUNT), &DEPOSIT_AMOUNT);
         assert_eq!(1, k.get_total_supporters());
     }
 
     #[test]
     fn test_workflow() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.withdraw(U128::from(ntoy(50)), kickstarter_id);
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_deposit(&SUPPORTER_ACCOUNT.to_string()), ntoy(150));
+        assert_eq!(kickstarter.total_deposited, ntoy(150));
+    }
+
+    #[test]
+    fn test_withdraw_excedent_after_the_katherine_fee() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+        _activate_kickstarter(&mut context, &mut contract, kickstarter_id);
+
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        let katherine_fee = kickstarter.katherine_fee.unwrap();
+        let total_tokens_to_release = kickstarter.total_tokens_to_release.unwrap();
+        assert!(katherine_fee > 0);
+
+        // The withdrawn fee is still committed, it is not refunded as excedent.
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.withdraw_katherine_fee(kickstarter_id);
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_available_katherine_fee(), 0);
+        assert_eq!(kickstarter.katherine_fee, Some(katherine_fee));
+
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.kickstarter_withdraw_excedent(kickstarter_id);
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.available_reward_tokens, katherine_fee + total_tokens_to_release);
+    }
+
+    #[test]
+    #[should_panic(expected = "No remaining excedent pTOKEN to withdraw!")]
+    fn test_withdraw_excedent_twice() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+        _activate_kickstarter(&mut context, &mut contract, kickstarter_id);
+
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.kickstarter_withdraw_excedent(kickstarter_id);
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.withdraw_katherine_fee(kickstarter_id);
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.kickstarter_withdraw_excedent(kickstarter_id);
+    }
+
+    #[test]
+    fn test_kickstarters_with_pending_interest() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+        _activate_kickstarter(&mut context, &mut contract, kickstarter_id);
+        assert_eq!(contract.kickstarters_with_pending_interest, 0);
+
+        // The stNEAR price doubled between the freeze and the unfreeze.
+        set_timestamp(&mut context, to_millis(UNFREEZE_TIME_IN_DAYS));
+        set_promise_result(&context, st_near_price_result(2 * NEAR));
+        contract.set_stnear_price_at_unfreeze(kickstarter_id, KEEPER_ACCOUNT.try_into().unwrap());
+        assert_eq!(contract.kickstarters_with_pending_interest, 1);
+
+        let mut kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        contract.kickstarter_withdraw(&mut kickstarter, 2 * NEAR, KICKSTARTER_OWNER_ACCOUNT.to_string());
+        assert_eq!(contract.kickstarters_with_pending_interest, 0);
+
+        // A failed transfer leaves the interest pending again.
+        let interest = kickstarter.get_stnear_withdraw(&WithdrawEntity::Kickstarter);
+        set_promise_result(&context, PromiseResult::Failed);
+        contract.kickstarter_withdraw_resolve_transfer(
+            kickstarter_id,
+            U128::from(interest),
+            KICKSTARTER_OWNER_ACCOUNT.try_into().unwrap(),
+        );
+        assert_eq!(contract.kickstarters_with_pending_interest, 1);
+    }
+
+    /// Creates the Kickstarters of a batch test with a single goal. Only the Kickstarters
+    /// marked as funded get the deposit that achieves the goal.
+    fn _new_batch_kickstarters(
+        context: &mut VMContext,
+        contract: &mut KatherineFundraising,
+        funded: Vec<bool>,
+    ) -> Vec<KickstarterIdJSON> {
+        let kickstarter_ids: Vec<KickstarterIdJSON> = funded
+            .iter()
+            .map(|_| {
+                let kickstarter_id = _new_kickstarter(context.clone(), contract);
+                _new_goal(contract, kickstarter_id, ntoy(100), ntoy(1));
+                kickstarter_id
+            })
+            .collect();
+        for (kickstarter_id, funded) in kickstarter_ids.iter().zip(funded) {
+            if funded {
+                _fund_kickstarter(context, contract, *kickstarter_id);
+                set_timestamp(context, to_millis(OPEN_TIME_IN_DAYS));
+                _supporter_deposit(context, contract, *kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+            }
+        }
+        set_timestamp(context, to_millis(CLOSE_TIME_IN_DAYS) + 1);
+        kickstarter_ids
+    }
+
+    fn get_statuses(results: Vec<BatchResultJSON>) -> Vec<BatchStatus> {
+        results.into_iter().map(|result| result.status).collect()
+    }
+
+    #[test]
+    fn test_process_kickstarters_callback_mixed_batch() {
+        let (mut context, mut contract) = contract_only_setup();
+        let ids = _new_batch_kickstarters(&mut context, &mut contract, vec![true, false, true]);
+        _activate_kickstarter(&mut context, &mut contract, ids[2]);
+
+        set_promise_result(&context, st_near_price_result(NEAR));
+        let results = contract.process_kickstarters_callback(
+            ids.clone(),
+            vec![],
+            KEEPER_ACCOUNT.try_into().unwrap(),
+        );
+        assert_eq!(
+            get_statuses(results),
+            vec![BatchStatus::Successful, BatchStatus::Failed, BatchStatus::Skipped]
+        );
+        assert_eq!(contract.internal_get_kickstarter(ids[0]).successful, Some(true));
+        assert_eq!(contract.internal_get_kickstarter(ids[0]).stnear_price_at_freeze, Some(NEAR));
+        assert_eq!(contract.internal_get_kickstarter(ids[1]).successful, None);
+    }
+
+    #[test]
+    fn test_process_kickstarters_callback_without_price() {
+        let (mut context, mut contract) = contract_only_setup();
+        let ids = _new_batch_kickstarters(&mut context, &mut contract, vec![true, true]);
+        _activate_kickstarter(&mut context, &mut contract, ids[1]);
+
+        // The results of the first call are kept in front of the callback results.
+        let previous = vec![BatchResultJSON::skipped(7, "Funding period is not over!")];
+        set_promise_result(&context, PromiseResult::Failed);
+        let results = contract.process_kickstarters_callback(
+            ids.clone(),
+            previous,
+            KEEPER_ACCOUNT.try_into().unwrap(),
+        );
+        assert_eq!(results[1].message, Some("Meta Pool is not available!".to_string()));
+        assert_eq!(
+            get_statuses(results),
+            vec![BatchStatus::Skipped, BatchStatus::Failed, BatchStatus::Skipped]
+        );
+        assert_eq!(contract.internal_get_kickstarter(ids[0]).successful, None);
+    }
+
+    #[test]
+    fn test_unfreeze_kickstarters_callback_mixed_batch() {
+        let (mut context, mut contract) = contract_only_setup();
+        let ids = _new_batch_kickstarters(&mut context, &mut contract, vec![true, true, true]);
+        for kickstarter_id in ids.iter() {
+            _activate_kickstarter(&mut context, &mut contract, *kickstarter_id);
+        }
+        set_timestamp(&mut context, to_millis(UNFREEZE_TIME_IN_DAYS) + 1);
+
+        set_promise_result(&context, st_near_price_result(2 * NEAR));
+        let results = contract.unfreeze_kickstarters_callback(
+            vec![ids[0]],
+            vec![],
+            KEEPER_ACCOUNT.try_into().unwrap(),
+        );
+        assert_eq!(get_statuses(results), vec![BatchStatus::Successful]);
+
+        set_promise_result(&context, PromiseResult::Failed);
+        let results = contract.unfreeze_kickstarters_callback(
+            vec![ids[0], ids[1]],
+            vec![],
+            KEEPER_ACCOUNT.try_into().unwrap(),
+        );
+        assert_eq!(get_statuses(results), vec![BatchStatus::Skipped, BatchStatus::Failed]);
+        assert_eq!(contract.internal_get_kickstarter(ids[0]).stnear_price_at_unfreeze, Some(2 * NEAR));
+        assert_eq!(contract.internal_get_kickstarter(ids[1]).stnear_price_at_unfreeze, None);
+
+        set_promise_result(&context, st_near_price_result(2 * NEAR));
+        let results = contract.unfreeze_kickstarters_callback(
+            ids.clone(),
+            vec![],
+            KEEPER_ACCOUNT.try_into().unwrap(),
+        );
+        assert_eq!(
+            get_statuses(results),
+            vec![BatchStatus::Skipped, BatchStatus::Successful, BatchStatus::Successful]
+        );
+    }
+
+    #[test]
+    fn test_get_kickstarters_to_process_from_index() {
+        let (mut context, mut contract) = contract_only_setup();
+        let ids = _new_batch_kickstarters(&mut context, &mut contract, vec![true, false, true]);
+
+        let first_page = contract.get_kickstarters_to_process(0, 2).unwrap();
+        assert_eq!(first_page.successful, vec![ids[0]]);
+        assert_eq!(first_page.unsuccessful, vec![ids[1]]);
+
+        let second_page = contract.get_kickstarters_to_process(2, 2).unwrap();
+        assert_eq!(second_page.successful, vec![ids[2]]);
+        assert!(second_page.unsuccessful.is_empty());
+
+        assert!(contract.get_kickstarters_to_process(3, 2).is_none());
+
+        // A processed Kickstarter is no longer returned.
+        _activate_kickstarter(&mut context, &mut contract, ids[2]);
+        let second_page = contract.get_kickstarters_to_process(2, 2).unwrap();
+        assert!(second_page.successful.is_empty());
+    }
+
+    #[test]
+    fn test_get_kickstarters_to_unfreeze_from_index() {
+        let (mut context, mut contract) = contract_only_setup();
+        let ids = _new_batch_kickstarters(&mut context, &mut contract, vec![true, true, true]);
+        for kickstarter_id in ids.iter() {
+            _activate_kickstarter(&mut context, &mut contract, *kickstarter_id);
+        }
+        assert_eq!(contract.get_kickstarters_to_unfreeze(0, 3), Some(vec![]));
+
+        set_timestamp(&mut context, to_millis(UNFREEZE_TIME_IN_DAYS) + 1);
+        set_promise_result(&context, st_near_price_result(2 * NEAR));
+        contract.set_stnear_price_at_unfreeze(ids[1], KEEPER_ACCOUNT.try_into().unwrap());
+
+        assert_eq!(contract.get_kickstarters_to_unfreeze(0, 2), Some(vec![ids[0]]));
+        assert_eq!(contract.get_kickstarters_to_unfreeze(2, 2), Some(vec![ids[2]]));
+        assert_eq!(contract.get_kickstarters_to_unfreeze(3, 2), None);
+    }
+
+    #[test]
+    fn test_linear_tokens_to_release_within_the_available_rewards() {
+        let (mut context, mut contract) = contract_only_setup();
+        let between_id = _new_kickstarter(context.clone(), &mut contract);
+        let above_id = _new_kickstarter(context.clone(), &mut contract);
+        for kickstarter_id in vec![between_id, above_id] {
+            _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+            _new_goal(&mut contract, kickstarter_id, ntoy(300), ntoy(2));
+            contract.set_reward_mode(kickstarter_id, RewardMode::Linear);
+        }
+        _fund_kickstarter(&mut context, &mut contract, between_id);
+        _fund_kickstarter(&mut context, &mut contract, above_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, between_id, SUPPORTER_ACCOUNT, ntoy(250));
+        _supporter_deposit(&mut context, &mut contract, above_id, SUPPORTER_ACCOUNT, ntoy(DEPOSITS_HARD_CAP));
+        _activate_kickstarter(&mut context, &mut contract, between_id);
+        _activate_kickstarter(&mut context, &mut contract, above_id);
+
+        // 250 stNEAR are 3/4 of the way to the last goal: 1.75 pTOKEN per stNEAR.
+        let kickstarter = contract.internal_get_kickstarter(between_id);
+        assert_eq!(kickstarter.total_tokens_to_release, Some(ntoy(250) * 7 / 4));
+        assert!(
+            kickstarter.total_tokens_to_release.unwrap() + kickstarter.katherine_fee.unwrap()
+                <= kickstarter.available_reward_tokens
+        );
+
+        // Above the last goal, the max tokens per stNEAR are released.
+        let kickstarter = contract.internal_get_kickstarter(above_id);
+        assert_eq!(kickstarter.total_tokens_to_release, Some(ntoy(DEPOSITS_HARD_CAP * 2)));
+        assert!(
+            kickstarter.total_tokens_to_release.unwrap() + kickstarter.katherine_fee.unwrap()
+                <= kickstarter.available_reward_tokens
+        );
+    }
+
+    #[test]
+    fn test_grant_role() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        assert!(contract.has_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap()));
+        assert!(!contract.has_role(Role::Robot, OPERATOR_ACCOUNT.try_into().unwrap()));
+        assert_eq!(
+            contract.get_role_members(Role::Operator, 0, 10),
+            vec![OPERATOR_ACCOUNT.to_string()]
+        );
+
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        _new_kickstarter(context.clone(), &mut contract);
+        assert_eq!(contract.get_total_kickstarters(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_grant_role_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.grant_role(Role::Operator, SUPPORTER_ACCOUNT.try_into().unwrap());
+    }
+
+    #[test]
+    fn test_revoke_role() {
         let (_context, mut contract) = contract_only_setup();
-        _new_kickstarter(_context, &mut contract);
-        let kickstarter_id = contract.kickstarters.len() - 1;
-        let mut k = contract.kickstarters.get(kickstarter_id).unwrap();
-        k.update_supporter_deposits(&String::from(SUPPORTER_ACCOUNT), &DEPOSIT_AMOUNT);
-        contract.create_goal(
-            k.id,
-            "test_goal".to_string(),
-            U128::from(100),
-            to_ts(START_TIME_IN_DAYS * 30), // WIP agregue para que compile
-            U128::from(200),
-            to_ts(START_TIME_IN_DAYS * 30),
-            to_ts(START_TIME_IN_DAYS * 50),
-        );
-        contract.withdraw(U128::from(50), k.id);
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        contract.revoke_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        assert!(!contract.has_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap()));
+        assert!(contract.get_role_members(Role::Operator, 0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin or Operator")]
+    fn test_create_kickstarter_after_the_role_is_revoked() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        contract.revoke_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        _new_kickstarter(context.clone(), &mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_revoke_role_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        contract.grant_role(Role::Robot, KEEPER_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.revoke_role(Role::Robot, KEEPER_ACCOUNT.try_into().unwrap());
+    }
+
+    #[test]
+    fn test_renounce_role() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::FeeCollector, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.renounce_role(Role::FeeCollector);
+        assert!(!contract.has_role(Role::FeeCollector, OPERATOR_ACCOUNT.try_into().unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "operator does not have the FeeCollector role")]
+    fn test_renounce_role_without_the_role() {
+        let (mut context, mut contract) = contract_only_setup();
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.renounce_role(Role::FeeCollector);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin or FeeCollector")]
+    fn test_withdraw_katherine_fee_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.withdraw_katherine_fee(kickstarter_id);
+    }
+
+    #[test]
+    fn test_ownership_transfer() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.propose_new_owner(OPERATOR_ACCOUNT.try_into().unwrap());
+        assert_eq!(contract.get_owner_id(), OWNER_ACCOUNT.to_string());
+        assert_eq!(contract.get_pending_owner_id(), Some(OPERATOR_ACCOUNT.to_string()));
+
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner_id(), OPERATOR_ACCOUNT.to_string());
+        assert_eq!(contract.get_pending_owner_id(), None);
+
+        // The new owner is the admin.
+        contract.grant_role(Role::Robot, KEEPER_ACCOUNT.try_into().unwrap());
+        assert!(contract.has_role(Role::Robot, KEEPER_ACCOUNT.try_into().unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_previous_owner_after_the_ownership_transfer() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.propose_new_owner(OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.accept_ownership();
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.grant_role(Role::Robot, KEEPER_ACCOUNT.try_into().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_propose_new_owner_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.propose_new_owner(OPERATOR_ACCOUNT.try_into().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner could accept the ownership")]
+    fn test_accept_ownership_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.propose_new_owner(OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner could accept the ownership")]
+    fn test_cancel_ownership_proposal() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.propose_new_owner(OPERATOR_ACCOUNT.try_into().unwrap());
+        contract.cancel_ownership_proposal();
+        assert_eq!(contract.get_pending_owner_id(), None);
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_cancel_ownership_proposal_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.propose_new_owner(OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.cancel_ownership_proposal();
+    }
+
+    #[test]
+    fn test_set_paused() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.set_paused(true, true);
+        assert!(contract.deposits_paused && contract.outflows_paused);
+
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.set_paused(false, false);
+        assert!(!contract.deposits_paused && !contract.outflows_paused);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin or Operator")]
+    fn test_set_paused_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.set_paused(true, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_operator_cannot_unpause() {
+        let (mut context, mut contract) = contract_only_setup();
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        contract.set_paused(false, true);
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.set_paused(false, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposits are paused!")]
+    fn test_deposit_while_paused() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.set_paused(true, false);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+    }
+
+    #[test]
+    fn test_set_kickstarter_paused() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.set_kickstarter_paused(kickstarter_id, false, true);
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert!(!kickstarter.deposits_paused && kickstarter.outflows_paused);
+        assert!(!contract.outflows_paused);
+
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.set_kickstarter_paused(kickstarter_id, false, false);
+        assert!(!contract.internal_get_kickstarter(kickstarter_id).outflows_paused);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_set_kickstarter_paused_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        contract.set_kickstarter_paused(kickstarter_id, true, true);
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.set_kickstarter_paused(kickstarter_id, false, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawals and claims are paused!")]
+    fn test_withdraw_while_the_kickstarter_is_paused() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.set_kickstarter_paused(kickstarter_id, false, true);
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.withdraw(U128::from(ntoy(50)), kickstarter_id);
+    }
+
+    #[test]
+    fn test_cancel_kickstarter() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.cancel_kickstarter(kickstarter_id, "The project pulled out".to_string());
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert!(kickstarter.is_cancelled());
+        assert_eq!(kickstarter.successful, Some(false));
+
+        // The Supporter gets all the stNEAR back, and the owner all the pTOKEN.
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.withdraw(U128::from(ntoy(200)), kickstarter_id);
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.kickstarter_withdraw_excedent(kickstarter_id);
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_deposit(&SUPPORTER_ACCOUNT.to_string()), 0);
+        assert_eq!(kickstarter.total_deposited, 0);
+        assert_eq!(kickstarter.available_reward_tokens, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_cancel_kickstarter_by_an_operator() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.cancel_kickstarter(kickstarter_id, "Not allowed".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_cancel_kickstarter_by_the_kickstarter_owner() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.cancel_kickstarter(kickstarter_id, "Not allowed".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Kickstarter was cancelled!")]
+    fn test_deposit_after_the_cancellation() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_predecessor(&mut context, OWNER_ACCOUNT);
+        contract.cancel_kickstarter(kickstarter_id, "The project pulled out".to_string());
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(200));
+    }
+
+    #[test]
+    #[should_panic(expected = "The funding period is over, Kickstarter must be evaluated!")]
+    fn test_cancel_kickstarter_after_the_funding_period() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        set_timestamp(&mut context, to_millis(CLOSE_TIME_IN_DAYS) + 1);
+        contract.cancel_kickstarter(kickstarter_id, "Too late".to_string());
+    }
+
+    #[test]
+    fn test_update_kickstarter_by_the_kickstarter_owner() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        let available_reward_tokens = contract.internal_get_kickstarter(kickstarter_id).available_reward_tokens;
+
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.update_kickstarter(
+            kickstarter_id,
+            Some("renamed".to_string()),
+            Some("renamed_slug".to_string()),
+            None, None, None, None, None, None, None,
+        );
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.name, "renamed".to_string());
+        assert_eq!(contract.kickstarter_id_by_slug.get(&"renamed_slug".to_string()), Some(kickstarter_id));
+        assert_eq!(kickstarter.get_number_of_goals(), 1);
+        assert_eq!(kickstarter.available_reward_tokens, available_reward_tokens);
+        assert!(kickstarter.enough_reward_tokens);
+
+        // A higher hard cap requires more reward tokens.
+        contract.update_kickstarter(
+            kickstarter_id,
+            None, None, None, None, None, None,
+            Some(U128::from(ntoy(2 * DEPOSITS_HARD_CAP))),
+            None, None,
+        );
+        assert!(!contract.internal_get_kickstarter(kickstarter_id).enough_reward_tokens);
+    }
+
+    #[test]
+    fn test_update_kickstarter_by_an_operator() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.update_kickstarter(
+            kickstarter_id,
+            None, None,
+            Some(OTHER_SUPPORTER_ACCOUNT.to_string()),
+            None, None, None, None, None, None,
+        );
+        assert_eq!(
+            contract.internal_get_kickstarter(kickstarter_id).owner_id,
+            OTHER_SUPPORTER_ACCOUNT.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin, operator or the Kickstarter owner")]
+    fn test_update_kickstarter_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.update_kickstarter(
+            kickstarter_id,
+            Some("renamed".to_string()),
+            None, None, None, None, None, None, None, None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Changes are not allow after the funding period started!")]
+    fn test_update_kickstarter_after_the_open() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS) + 1);
+        contract.update_kickstarter(
+            kickstarter_id,
+            Some("renamed".to_string()),
+            None, None, None, None, None, None, None, None,
+        );
+    }
+
+    /// The depositor and the beneficiary cover their storage, and the depositor sends the stNEAR.
+    fn _deposit_on_behalf(
+        context: &mut VMContext,
+        contract: &mut KatherineFundraising,
+        kickstarter_id: KickstarterId,
+        depositor_id: &str,
+        beneficiary_id: &str,
+        amount: Balance,
+    ) {
+        set_attached_deposit(context, ntoy(1));
+        contract.storage_deposit(Some(depositor_id.try_into().unwrap()), None);
+        contract.storage_deposit(Some(beneficiary_id.try_into().unwrap()), None);
+        set_attached_deposit(context, 0);
+        set_predecessor(context, METAPOOL_CONTRACT_ADDRESS);
+        contract.ft_on_transfer(
+            depositor_id.try_into().unwrap(),
+            U128::from(amount),
+            format!(r#"{{"kickstarter_id":{},"beneficiary":"{}"}}"#, kickstarter_id, beneficiary_id),
+        );
+    }
+
+    #[test]
+    fn test_deposit_on_behalf() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _deposit_on_behalf(
+            &mut context,
+            &mut contract,
+            kickstarter_id,
+            OTHER_SUPPORTER_ACCOUNT,
+            SUPPORTER_ACCOUNT,
+            ntoy(200),
+        );
+
+        // The deposit belongs to the beneficiary, the depositor is only recorded.
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_deposit(&SUPPORTER_ACCOUNT.to_string()), ntoy(200));
+        assert_eq!(kickstarter.get_deposit(&OTHER_SUPPORTER_ACCOUNT.to_string()), 0);
+        assert_eq!(
+            kickstarter
+                .deposits_on_behalf
+                .get(&(OTHER_SUPPORTER_ACCOUNT.to_string(), SUPPORTER_ACCOUNT.to_string())),
+            Some(ntoy(200))
+        );
+        assert!(contract.supporters.get(&SUPPORTER_ACCOUNT.to_string()).is_some());
+
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.withdraw(U128::from(ntoy(50)), kickstarter_id);
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_deposit(&SUPPORTER_ACCOUNT.to_string()), ntoy(150));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not available amount!")]
+    fn test_withdraw_by_the_depositor_on_behalf() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _deposit_on_behalf(
+            &mut context,
+            &mut contract,
+            kickstarter_id,
+            OTHER_SUPPORTER_ACCOUNT,
+            SUPPORTER_ACCOUNT,
+            ntoy(200),
+        );
+
+        set_predecessor(&mut context, OTHER_SUPPORTER_ACCOUNT);
+        contract.withdraw(U128::from(ntoy(50)), kickstarter_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "supporter must cover the storage of the deposit on behalf")]
+    fn test_deposit_on_behalf_without_the_depositor_storage() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(&mut context, &mut contract, kickstarter_id);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        set_attached_deposit(&mut context, ntoy(1));
+        contract.storage_deposit(Some(SUPPORTER_ACCOUNT.try_into().unwrap()), None);
+        set_attached_deposit(&mut context, 0);
+        set_predecessor(&mut context, METAPOOL_CONTRACT_ADDRESS);
+        contract.ft_on_transfer(
+            OTHER_SUPPORTER_ACCOUNT.try_into().unwrap(),
+            U128::from(ntoy(200)),
+            format!(r#"{{"kickstarter_id":{},"beneficiary":"{}"}}"#, kickstarter_id, SUPPORTER_ACCOUNT),
+        );
+    }
+
+    fn goal_input(desired_amount: Balance, tokens_to_release_per_stnear: Balance) -> GoalInput {
+        GoalInput {
+            name: "goal".to_string(),
+            desired_amount: U128::from(desired_amount),
+            unfreeze_timestamp: to_millis(UNFREEZE_TIME_IN_DAYS),
+            tokens_to_release_per_stnear: U128::from(tokens_to_release_per_stnear),
+            cliff_timestamp: to_millis(CLIFF_TIME_IN_DAYS),
+            end_timestamp: to_millis(END_TIME_IN_DAYS),
+            min_supporters: 0,
+            max_supporter_share: None,
+            vesting_schedule: VestingSchedule::default(),
+        }
+    }
+
+    #[test]
+    fn test_set_goals() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(500), ntoy(2));
+
+        set_predecessor(&mut context, KICKSTARTER_OWNER_ACCOUNT);
+        contract.set_goals(
+            kickstarter_id,
+            vec![goal_input(ntoy(100), ntoy(1)), goal_input(ntoy(300), ntoy(2))],
+        );
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_number_of_goals(), 2);
+        assert_eq!(kickstarter.get_goal_by_id(0).desired_amount, ntoy(100));
+        assert_eq!(kickstarter.get_goal_by_id(1).id, 1);
+        assert_eq!(kickstarter.get_goal_by_id(1).desired_amount, ntoy(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin, operator or the Kickstarter owner")]
+    fn test_set_goals_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.set_goals(kickstarter_id, vec![goal_input(ntoy(100), ntoy(1))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Kickstarter config")]
+    fn test_set_goals_out_of_order() {
+        let (_context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(_context, &mut contract);
+        contract.set_goals(
+            kickstarter_id,
+            vec![goal_input(ntoy(300), ntoy(1)), goal_input(ntoy(100), ntoy(2))],
+        );
+    }
+
+    #[test]
+    fn test_update_goal() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        _new_goal(&mut contract, kickstarter_id, ntoy(300), ntoy(2));
+
+        contract.grant_role(Role::Operator, OPERATOR_ACCOUNT.try_into().unwrap());
+        set_predecessor(&mut context, OPERATOR_ACCOUNT);
+        contract.update_goal(kickstarter_id, 0, goal_input(ntoy(200), ntoy(1)));
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        assert_eq!(kickstarter.get_number_of_goals(), 2);
+        assert_eq!(kickstarter.get_goal_by_id(0).desired_amount, ntoy(200));
+        assert_eq!(kickstarter.get_goal_by_id(1).desired_amount, ntoy(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin, operator or the Kickstarter owner")]
+    fn test_update_goal_unauthorized() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.update_goal(kickstarter_id, 0, goal_input(ntoy(200), ntoy(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Action not allow after funding period is open!")]
+    fn test_update_goal_after_the_open() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+        _new_goal(&mut contract, kickstarter_id, ntoy(100), ntoy(1));
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        contract.update_goal(kickstarter_id, 0, goal_input(ntoy(200), ntoy(1)));
     }
 }
@@ -0,0 +1,56 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Off-chain like information of the Kickstarter, following the NEP-177 reference style.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct KickstarterMetadata {
+    /// Short description of the project
+    pub description: Option<String>,
+    pub website: Option<String>,
+    pub social_links: Vec<String>,
+    pub logo_url: Option<String>,
+    /// URL to a JSON file with more info
+    pub reference: Option<String>,
+    /// Base64-encoded sha256 hash of the JSON from the reference field
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+impl KickstarterMetadata {
+    pub fn assert_valid(&self) {
+        if let Some(description) = &self.description {
+            assert!(
+                description.len() <= MAX_METADATA_DESCRIPTION_LENGTH,
+                "Description cannot exceed {} bytes",
+                MAX_METADATA_DESCRIPTION_LENGTH
+            );
+        }
+        assert!(
+            self.social_links.len() <= MAX_METADATA_SOCIAL_LINKS,
+            "Too many social links, max number is {}",
+            MAX_METADATA_SOCIAL_LINKS
+        );
+        let urls = self
+            .website
+            .iter()
+            .chain(self.logo_url.iter())
+            .chain(self.reference.iter())
+            .chain(self.social_links.iter());
+        for url in urls {
+            assert!(
+                url.len() <= MAX_METADATA_URL_LENGTH,
+                "URLs cannot exceed {} bytes",
+                MAX_METADATA_URL_LENGTH
+            );
+        }
+        assert_eq!(
+            self.reference.is_some(),
+            self.reference_hash.is_some(),
+            "Reference and reference hash must be present together"
+        );
+        if let Some(reference_hash) = &self.reference_hash {
+            assert_eq!(reference_hash.0.len(), 32, "Hash has to be 32 bytes");
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::*;
+use near_sdk::near_bindgen;
+use near_sdk::collections::TreeMap;
+
+/// Layouts of the contract state before the versioned Kickstarters. These structs are
+/// frozen, do NOT modify them. They are only used to read the old state during the migration.
+///
+/// The V2 Kickstarter, its Goals and the contract state are the layout of the first release
+/// with versioned Kickstarters, including every field added until that release. The migration
+/// only supports the upgrade from V1 to that release in a single deploy. Once V2 is deployed,
+/// freeze the Kickstarter and the Goal here as KickstarterV2 and GoalV2, and add a V3 variant
+/// for the next layout change.
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct GoalV1 {
+    pub id: GoalId,
+    pub name: String,
+    pub desired_amount: Balance,
+    pub unfreeze_timestamp: EpochMillis,
+    pub tokens_to_release_per_stnear: Balance,
+    pub cliff_timestamp: EpochMillis,
+    pub end_timestamp: EpochMillis,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct KickstarterV1 {
+    pub id: KickstarterId,
+    pub name: String,
+    pub slug: String,
+    pub goals: Vector<GoalV1>,
+    pub owner_id: AccountId,
+    pub winner_goal_id: Option<u8>,
+    pub katherine_fee: Option<Balance>,
+    pub total_tokens_to_release: Option<Balance>,
+    pub deposits: UnorderedMap<SupporterId, Balance>,
+    pub rewards_withdraw: UnorderedMap<SupporterId, Balance>,
+    pub stnear_withdraw: UnorderedMap<WithdrawEntity, Balance>,
+    pub total_deposited: Balance,
+    pub deposits_hard_cap: Balance,
+    pub max_tokens_to_release_per_stnear: Balance,
+    pub enough_reward_tokens: bool,
+    pub active: bool,
+    pub successful: Option<bool>,
+    pub stnear_price_at_freeze: Option<Balance>,
+    pub stnear_price_at_unfreeze: Option<Balance>,
+    pub creation_timestamp: EpochMillis,
+    pub open_timestamp: EpochMillis,
+    pub close_timestamp: EpochMillis,
+    pub token_contract_address: AccountId,
+    pub available_reward_tokens: Balance,
+    pub token_contract_decimals: u8,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct KatherineFundraisingV1 {
+    pub owner_id: AccountId,
+    pub supporters: UnorderedMap<SupporterId, Supporter>,
+    pub kickstarters: Vector<KickstarterV1>,
+    pub kickstarter_id_by_slug: UnorderedMap<String, KickstarterId>,
+    pub min_deposit_amount: Balance,
+    pub metapool_contract_address: AccountId,
+    pub katherine_fee_percent: BasisPoints,
+    pub max_goals_per_kickstarter: u8,
+    pub active_projects: UnorderedSet<KickstarterId>,
+}
+
+impl GoalV1 {
+    /// The V1 goals had no extra success conditions and a linear vesting.
+    pub fn into_current(self) -> Goal {
+        Goal {
+            id: self.id,
+            name: self.name,
+            desired_amount: self.desired_amount,
+            unfreeze_timestamp: self.unfreeze_timestamp,
+            tokens_to_release_per_stnear: self.tokens_to_release_per_stnear,
+            cliff_timestamp: self.cliff_timestamp,
+            end_timestamp: self.end_timestamp,
+            min_supporters: 0,
+            max_supporter_share: None,
+            vesting_schedule: VestingSchedule::default(),
+        }
+    }
+}
+
+impl KickstarterV1 {
+    /// The V1 Kickstarters used the global Katherine fee percent.
+    /// The reward tokens of the V1 Kickstarters are attributed to the owner.
+    pub fn into_current(self, katherine_fee_percent: BasisPoints) -> Kickstarter {
+        let prefix = self.id.to_string();
+        let mut reward_funders = UnorderedMap::new(Keys::RewardFunders.as_prefix(&prefix).as_bytes());
+        if self.available_reward_tokens > 0 {
+            reward_funders.insert(&self.owner_id, &self.available_reward_tokens);
+        }
+        let mut deposit_amounts = TreeMap::new(Keys::DepositAmounts.as_prefix(&prefix).as_bytes());
+        for deposit in self.deposits.values() {
+            let count = deposit_amounts.get(&deposit).unwrap_or(0);
+            deposit_amounts.insert(&deposit, &(count + 1));
+        }
+        // Same storage prefix, the goals are rewritten in place with the current layout.
+        let old_goals: Vec<GoalV1> = self.goals.iter().collect();
+        let mut goals: Vector<Goal> = Vector::new(Keys::Goals.as_prefix(&prefix).as_bytes());
+        for old_goal in old_goals {
+            goals.push(&old_goal.into_current());
+        }
+        Kickstarter {
+            id: self.id,
+            name: self.name,
+            slug: self.slug,
+            goals,
+            owner_id: self.owner_id,
+            winner_goal_id: self.winner_goal_id,
+            katherine_fee: self.katherine_fee,
+            katherine_fee_withdraw: 0,
+            total_tokens_to_release: self.total_tokens_to_release,
+            deposits: self.deposits,
+            deposit_amounts,
+            rewards_withdraw: self.rewards_withdraw,
+            stnear_withdraw: self.stnear_withdraw,
+            total_deposited: self.total_deposited,
+            deposits_hard_cap: self.deposits_hard_cap,
+            max_tokens_to_release_per_stnear: self.max_tokens_to_release_per_stnear,
+            enough_reward_tokens: self.enough_reward_tokens,
+            active: self.active,
+            successful: self.successful,
+            cancel_reason: None,
+            stnear_price_at_freeze: self.stnear_price_at_freeze,
+            stnear_price_at_unfreeze: self.stnear_price_at_unfreeze,
+            creation_timestamp: self.creation_timestamp,
+            open_timestamp: self.open_timestamp,
+            close_timestamp: self.close_timestamp,
+            token_contract_address: self.token_contract_address,
+            available_reward_tokens: self.available_reward_tokens,
+            token_contract_decimals: self.token_contract_decimals,
+            katherine_fee_percent,
+            deposits_paused: false,
+            outflows_paused: false,
+            referral_fee_percent: 0,
+            referrers: UnorderedMap::new(Keys::Referrers.as_prefix(&prefix).as_bytes()),
+            referred_deposits: UnorderedMap::new(Keys::ReferredDeposits.as_prefix(&prefix).as_bytes()),
+            supporter_referred_deposits: UnorderedMap::new(
+                Keys::SupporterReferredDeposits.as_prefix(&prefix).as_bytes(),
+            ),
+            total_referred: 0,
+            referral_rewards_withdraw: UnorderedMap::new(
+                Keys::ReferralWithdraws.as_prefix(&prefix).as_bytes(),
+            ),
+            deposits_on_behalf: UnorderedMap::new(Keys::DepositsOnBehalf.as_prefix(&prefix).as_bytes()),
+            deposit_tiers: Vec::new(),
+            tier_deposits: UnorderedMap::new(Keys::TierDeposits.as_prefix(&prefix).as_bytes()),
+            total_weighted_deposits: 0,
+            reward_funders,
+            funders_allowlist: UnorderedSet::new(Keys::FundersAllowlist.as_prefix(&prefix).as_bytes()),
+            reward_mode: RewardMode::Step,
+            keeper_bounty: 0,
+            max_keeper_bounties: 0,
+            total_keeper_bounties: 0,
+            keeper_bounties_reserve: 0,
+            keeper_rewards: UnorderedMap::new(Keys::KeeperRewards.as_prefix(&prefix).as_bytes()),
+            time_weighted: false,
+            time_weighted_deposits: UnorderedMap::new(
+                Keys::TimeWeightedDeposits.as_prefix(&prefix).as_bytes(),
+            ),
+            total_time_weighted_deposits: 0,
+        }
+    }
+}
+
+#[near_bindgen]
+impl KatherineFundraising {
+    /// Converts the V1 state, deployed before the versioned Kickstarters, into the current layout.
+    /// Call it in the same transaction as the deploy of the new code.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: KatherineFundraisingV1 = env::state_read().expect("Old state not found");
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == env::current_account_id() || predecessor_id == old.owner_id,
+            "only allowed for admin"
+        );
+
+        let mut contract = Self {
+            owner_id: old.owner_id,
+            pending_owner_id: None,
+            supporters: old.supporters,
+            // Same storage prefix, the Kickstarters are rewritten in place with the version tag.
+            kickstarters: Vector::new(Keys::Kickstarters),
+            kickstarter_id_by_slug: old.kickstarter_id_by_slug,
+            kickstarter_metadata: UnorderedMap::new(Keys::KickstarterMetadata),
+            min_deposit_amount: old.min_deposit_amount,
+            metapool_contract_address: old.metapool_contract_address,
+            katherine_fee_percent: old.katherine_fee_percent,
+            max_goals_per_kickstarter: old.max_goals_per_kickstarter,
+            active_projects: old.active_projects,
+            operators: UnorderedSet::new(Keys::Operators),
+            fee_collectors: UnorderedSet::new(Keys::FeeCollectors),
+            robots: UnorderedSet::new(Keys::Robots),
+            deposits_paused: false,
+            outflows_paused: false,
+            storage_deposits: UnorderedMap::new(Keys::StorageDeposits),
+            kickstarters_with_pending_interest: 0,
+        };
+        for index in 0..old.kickstarters.len() {
+            let old_kickstarter = old.kickstarters.get(index).expect("Kickstarter not found");
+            let kickstarter = old_kickstarter.into_current(contract.katherine_fee_percent);
+            contract.internal_update_pending_interest(false, &kickstarter);
+            contract.internal_save_kickstarter(&kickstarter);
+        }
+        log!("MIGRATION: {} kickstarters migrated", contract.kickstarters.len());
+        contract
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+
+    const OPEN_TIMESTAMP: EpochMillis = 1_000;
+    const CLOSE_TIMESTAMP: EpochMillis = 2_000;
+
+    fn old_kickstarter(id: KickstarterId, owner_id: &AccountId) -> KickstarterV1 {
+        let prefix = id.to_string();
+        KickstarterV1 {
+            id,
+            name: format!("kickstarter_{}", id),
+            slug: format!("slug_{}", id),
+            goals: Vector::new(Keys::Goals.as_prefix(&prefix).as_bytes()),
+            owner_id: owner_id.clone(),
+            winner_goal_id: None,
+            katherine_fee: None,
+            total_tokens_to_release: None,
+            deposits: UnorderedMap::new(Keys::Deposits.as_prefix(&prefix).as_bytes()),
+            rewards_withdraw: UnorderedMap::new(Keys::RewardWithdraws.as_prefix(&prefix).as_bytes()),
+            stnear_withdraw: UnorderedMap::new(Keys::StnearWithdraws.as_prefix(&prefix).as_bytes()),
+            total_deposited: 0,
+            deposits_hard_cap: 10 * NEAR,
+            max_tokens_to_release_per_stnear: 2 * NEAR,
+            enough_reward_tokens: true,
+            active: true,
+            successful: None,
+            stnear_price_at_freeze: None,
+            stnear_price_at_unfreeze: None,
+            creation_timestamp: 0,
+            open_timestamp: OPEN_TIMESTAMP,
+            close_timestamp: CLOSE_TIMESTAMP,
+            token_contract_address: accounts(3).to_string(),
+            available_reward_tokens: 25 * NEAR,
+            token_contract_decimals: 24,
+        }
+    }
+
+    fn old_goal(id: GoalId) -> GoalV1 {
+        GoalV1 {
+            id,
+            name: format!("goal_{}", id),
+            desired_amount: (id as u128 + 1) * NEAR,
+            unfreeze_timestamp: CLOSE_TIMESTAMP + 1_000,
+            tokens_to_release_per_stnear: NEAR,
+            cliff_timestamp: CLOSE_TIMESTAMP + 2_000,
+            end_timestamp: CLOSE_TIMESTAMP + 3_000,
+        }
+    }
+
+    /// Writes a fixture with the V1 layout: 2 kickstarters, with 2 goals and 2 supporters each.
+    fn write_old_state(owner_id: &AccountId) {
+        let mut old = KatherineFundraisingV1 {
+            owner_id: owner_id.clone(),
+            supporters: UnorderedMap::new(Keys::Supporters),
+            kickstarters: Vector::new(Keys::Kickstarters),
+            kickstarter_id_by_slug: UnorderedMap::new(Keys::KickstarterId),
+            min_deposit_amount: 2,
+            metapool_contract_address: accounts(4).to_string(),
+            katherine_fee_percent: 100,
+            max_goals_per_kickstarter: 5,
+            active_projects: UnorderedSet::new(Keys::Active),
+        };
+        for id in 0..2 {
+            let mut kickstarter = old_kickstarter(id, owner_id);
+            for goal_id in 0..2 {
+                kickstarter.goals.push(&old_goal(goal_id));
+            }
+            for supporter_id in [accounts(1).to_string(), accounts(2).to_string()].iter() {
+                let amount = (id as u128 + 1) * NEAR;
+                kickstarter.deposits.insert(supporter_id, &amount);
+                kickstarter.total_deposited += amount;
+                let mut supporter = old
+                    .supporters
+                    .get(supporter_id)
+                    .unwrap_or(Supporter::new(supporter_id));
+                supporter.supported_projects.insert(&id);
+                old.supporters.insert(supporter_id, &supporter);
+            }
+            old.kickstarter_id_by_slug.insert(&kickstarter.slug, &id);
+            old.active_projects.insert(&id);
+            old.kickstarters.push(&kickstarter);
+        }
+        env::state_write(&old);
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let owner_id = accounts(0).to_string();
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        write_old_state(&owner_id);
+
+        let contract = KatherineFundraising::migrate();
+
+        assert_eq!(contract.owner_id, owner_id);
+        assert_eq!(contract.pending_owner_id, None);
+        assert_eq!(contract.min_deposit_amount, 2);
+        assert_eq!(contract.katherine_fee_percent, 100);
+        assert_eq!(contract.get_total_kickstarters(), 2);
+        assert_eq!(contract.active_projects.len(), 2);
+        assert_eq!(contract.supporters.len(), 2);
+        for id in 0..2 {
+            let kickstarter = contract.internal_get_kickstarter(id);
+            assert_eq!(kickstarter.id, id);
+            assert_eq!(kickstarter.slug, format!("slug_{}", id));
+            assert_eq!(contract.get_kickstarter_id_from_slug(kickstarter.slug.clone()), id);
+            assert_eq!(kickstarter.available_reward_tokens, 25 * NEAR);
+            assert_eq!(kickstarter.reward_funders.get(&owner_id), Some(25 * NEAR));
+            assert!(kickstarter.enough_reward_tokens);
+            assert_eq!(kickstarter.katherine_fee_percent, 100);
+            assert!(!kickstarter.deposits_paused && !kickstarter.outflows_paused);
+
+            assert_eq!(kickstarter.get_number_of_goals(), 2);
+            for goal_id in 0..2 {
+                assert_eq!(kickstarter.get_goal_by_id(goal_id), old_goal(goal_id).into_current());
+            }
+
+            let amount = (id as u128 + 1) * NEAR;
+            assert_eq!(kickstarter.get_total_supporters(), 2);
+            assert_eq!(kickstarter.total_deposited, 2 * amount);
+            assert_eq!(kickstarter.get_deposit(&accounts(1).to_string()), amount);
+            assert_eq!(kickstarter.get_deposit(&accounts(2).to_string()), amount);
+        }
+        let supported_projects = contract.get_supported_projects(accounts(1));
+        assert_eq!(supported_projects.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed for admin")]
+    fn test_migrate_only_admin() {
+        let owner_id = accounts(0).to_string();
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        write_old_state(&owner_id);
+        KatherineFundraising::migrate();
+    }
+}
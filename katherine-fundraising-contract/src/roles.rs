@@ -0,0 +1,158 @@
+use crate::*;
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{near_bindgen, AccountId};
+
+/// The Katherine owner is the super-admin, and it is the only account that could grant
+/// and revoke roles. The owner implicitly has all the roles.
+
+impl KatherineFundraising {
+    pub(crate) fn role_members(&self, role: Role) -> &UnorderedSet<AccountId> {
+        match role {
+            Role::Operator => &self.operators,
+            Role::FeeCollector => &self.fee_collectors,
+            Role::Robot => &self.robots,
+        }
+    }
+
+    fn role_members_mut(&mut self, role: Role) -> &mut UnorderedSet<AccountId> {
+        match role {
+            Role::Operator => &mut self.operators,
+            Role::FeeCollector => &mut self.fee_collectors,
+            Role::Robot => &mut self.robots,
+        }
+    }
+
+    pub(crate) fn is_admin(&self, account_id: &AccountId) -> bool {
+        &self.owner_id == account_id
+    }
+
+    pub(crate) fn internal_has_role(&self, role: Role, account_id: &AccountId) -> bool {
+        self.is_admin(account_id) || self.role_members(role).contains(account_id)
+    }
+
+    #[inline]
+    pub(crate) fn assert_only_role(&self, role: Role) {
+        assert!(
+            self.internal_has_role(role, &env::predecessor_account_id()),
+            "only allowed for admin or {:?}",
+            role
+        );
+    }
+
+    #[inline]
+    pub(crate) fn assert_only_operator(&self) {
+        self.assert_only_role(Role::Operator);
+    }
+
+    #[inline]
+    pub(crate) fn assert_only_fee_collector(&self) {
+        self.assert_only_role(Role::FeeCollector);
+    }
+
+    #[inline]
+    pub(crate) fn assert_only_robot(&self) {
+        self.assert_only_role(Role::Robot);
+    }
+
+    /// Kickstarter changes are allowed for operators and for the Kickstarter owner.
+    #[inline]
+    pub(crate) fn assert_operator_or_kickstarter_owner(&self, kickstarter: &Kickstarter) {
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.internal_has_role(Role::Operator, &account_id)
+                || kickstarter.owner_id == account_id,
+            "only allowed for admin, operator or the Kickstarter owner"
+        );
+    }
+}
+
+#[near_bindgen]
+impl KatherineFundraising {
+    pub fn grant_role(&mut self, role: Role, account_id: ValidAccountId) {
+        self.assert_only_admin();
+        let account_id: AccountId = account_id.into();
+        if self.role_members_mut(role).insert(&account_id) {
+            log!("ROLE: {:?} granted to {}", role, account_id);
+        } else {
+            panic!("{} already has the {:?} role", account_id, role);
+        }
+    }
+
+    pub fn revoke_role(&mut self, role: Role, account_id: ValidAccountId) {
+        self.assert_only_admin();
+        let account_id: AccountId = account_id.into();
+        if self.role_members_mut(role).remove(&account_id) {
+            log!("ROLE: {:?} revoked from {}", role, account_id);
+        } else {
+            panic!("{} does not have the {:?} role", account_id, role);
+        }
+    }
+
+    /// Leave a role without the intervention of the admin, e.g. for a compromised key.
+    pub fn renounce_role(&mut self, role: Role) {
+        let account_id = env::predecessor_account_id();
+        if self.role_members_mut(role).remove(&account_id) {
+            log!("ROLE: {:?} renounced by {}", role, account_id);
+        } else {
+            panic!("{} does not have the {:?} role", account_id, role);
+        }
+    }
+
+    /// First step of the ownership transfer, the nominee must call **accept_ownership**.
+    pub fn propose_new_owner(&mut self, new_owner_id: ValidAccountId) {
+        self.assert_only_admin();
+        let new_owner_id: AccountId = new_owner_id.into();
+        assert!(
+            new_owner_id != self.owner_id,
+            "The proposed owner is already the owner"
+        );
+        log!("OWNER: {} proposed {} as new owner", self.owner_id, new_owner_id);
+        self.pending_owner_id = Some(new_owner_id);
+    }
+
+    pub fn cancel_ownership_proposal(&mut self) {
+        self.assert_only_admin();
+        match self.pending_owner_id.take() {
+            Some(pending_owner_id) => {
+                log!("OWNER: proposal for {} cancelled", pending_owner_id);
+            }
+            None => panic!("There is no pending owner"),
+        }
+    }
+
+    /// Second step of the ownership transfer, only for the pending owner.
+    pub fn accept_ownership(&mut self) {
+        let account_id = env::predecessor_account_id();
+        assert_eq!(
+            self.pending_owner_id.as_ref(),
+            Some(&account_id),
+            "Only the pending owner could accept the ownership"
+        );
+        log!("OWNER: ownership transferred from {} to {}", self.owner_id, account_id);
+        self.owner_id = account_id;
+        self.pending_owner_id = None;
+    }
+
+    pub fn get_owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_pending_owner_id(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+
+    pub fn has_role(&self, role: Role, account_id: ValidAccountId) -> bool {
+        self.internal_has_role(role, account_id.as_ref())
+    }
+
+    pub fn get_role_members(&self, role: Role, from_index: u32, limit: u32) -> Vec<AccountId> {
+        let members = self.role_members(role).as_vector();
+        let members_len = members.len();
+        let start: u64 = from_index.into();
+        let mut result: Vec<AccountId> = Vec::new();
+        for index in start..std::cmp::min(start + limit as u64, members_len) {
+            result.push(members.get(index).unwrap());
+        }
+        result
+    }
+}
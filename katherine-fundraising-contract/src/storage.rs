@@ -0,0 +1,329 @@
+use crate::*;
+use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::{assert_one_yocto, env, log, near_bindgen, AccountId, Balance, Promise};
+
+use near_contract_standards::storage_management::{
+    StorageBalance, StorageBalanceBounds, StorageManagement,
+};
+
+/// Supporters pay for their own storage. The used storage is estimated from the number of
+/// supported projects, so it is released when the Supporter is removed. The audit entries of
+/// the deposits made for another account are kept, so the depositor pays them for good.
+
+impl KatherineFundraising {
+    pub(crate) fn storage_cost(&self, supported_projects: u64) -> Balance {
+        let bytes = SUPPORTER_STORAGE_BYTES + supported_projects * SUPPORTED_PROJECT_STORAGE_BYTES;
+        bytes as Balance * env::storage_byte_cost()
+    }
+
+    pub(crate) fn internal_storage_deposit_of(&self, account_id: &AccountId) -> Balance {
+        self.storage_deposits.get(account_id).unwrap_or(0)
+    }
+
+    pub(crate) fn internal_storage_used_by(&self, account_id: &AccountId) -> Balance {
+        match self.supporters.get(account_id) {
+            Some(supporter) => self.storage_cost(supporter.supported_projects.len()),
+            None => 0,
+        }
+    }
+
+    /// Storage that the Supporter must deposit before supporting the Kickstarter.
+    pub(crate) fn internal_missing_storage(
+        &self,
+        supporter_id: &AccountId,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        let supporter = self.internal_get_supporter(supporter_id);
+        if supporter.is_supporting(kickstarter.id) {
+            return 0;
+        }
+        self.storage_cost(supporter.supported_projects.len() + 1)
+            .saturating_sub(self.internal_storage_deposit_of(supporter_id))
+    }
+
+    fn internal_storage_available(&self, account_id: &AccountId) -> Balance {
+        self.internal_storage_deposit_of(account_id)
+            .saturating_sub(self.internal_storage_used_by(account_id))
+    }
+
+    /// Storage that the depositor must deposit before depositing for the Supporter, only the
+    /// first deposit for each Supporter adds an audit entry.
+    pub(crate) fn internal_missing_storage_on_behalf(
+        &self,
+        depositor_id: &AccountId,
+        supporter_id: &SupporterId,
+        kickstarter: &Kickstarter,
+    ) -> Balance {
+        if depositor_id == supporter_id
+            || kickstarter.deposits_on_behalf.get(&(depositor_id.clone(), supporter_id.clone())).is_some()
+        {
+            return 0;
+        }
+        let cost = DEPOSIT_ON_BEHALF_STORAGE_BYTES as Balance * env::storage_byte_cost();
+        cost.saturating_sub(self.internal_storage_available(depositor_id))
+    }
+
+    /// Takes the storage of a new audit entry from the depositor storage balance.
+    pub(crate) fn internal_storage_charge_on_behalf(&mut self, depositor_id: &AccountId) {
+        let cost = DEPOSIT_ON_BEHALF_STORAGE_BYTES as Balance * env::storage_byte_cost();
+        let total = self.internal_storage_deposit_of(depositor_id);
+        assert!(
+            self.internal_storage_available(depositor_id) >= cost,
+            "{} must cover the storage of the deposit on behalf with storage_deposit",
+            depositor_id
+        );
+        self.storage_deposits.insert(depositor_id, &(total - cost));
+        log!("STORAGE: {} charged to {} for a deposit on behalf", cost, depositor_id);
+    }
+
+    pub(crate) fn internal_storage_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        let new_total = self.internal_storage_deposit_of(account_id) + amount;
+        self.storage_deposits.insert(account_id, &new_total);
+        log!("STORAGE: {} deposited for {}", amount, account_id);
+    }
+
+    fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        self.storage_deposits.get(account_id).map(|total| StorageBalance {
+            total: U128::from(total),
+            available: U128::from(self.internal_storage_available(account_id)),
+        })
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for KatherineFundraising {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<ValidAccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id: AccountId = account_id
+            .map(|account_id| account_id.into())
+            .unwrap_or_else(env::predecessor_account_id);
+        let min_balance = self.storage_balance_bounds().min.0;
+        let is_registered = self.storage_deposits.get(&account_id).is_some();
+
+        if registration_only == Some(true) {
+            let refund = if is_registered {
+                amount
+            } else {
+                assert!(
+                    amount >= min_balance,
+                    "The attached deposit is less than the minimum storage balance"
+                );
+                self.internal_storage_deposit(&account_id, min_balance);
+                amount - min_balance
+            };
+            if refund > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(refund);
+            }
+        } else {
+            assert!(
+                is_registered || amount >= min_balance,
+                "The attached deposit is less than the minimum storage balance"
+            );
+            self.internal_storage_deposit(&account_id, amount);
+        }
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let storage_balance = self
+            .internal_storage_balance_of(&account_id)
+            .expect("The account is not registered");
+        let amount = amount.map(|amount| amount.0).unwrap_or(storage_balance.available.0);
+        assert!(
+            amount <= storage_balance.available.0,
+            "The amount is greater than the available storage balance"
+        );
+        if amount > 0 {
+            let new_total = storage_balance.total.0 - amount;
+            self.storage_deposits.insert(&account_id, &new_total);
+            Promise::new(account_id.clone()).transfer(amount);
+            log!("STORAGE: {} withdrawn by {}", amount, account_id);
+        }
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    /// The force option is not supported, Supporters with deposits or rewards cannot unregister.
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        assert!(force != Some(true), "Force unregister is not supported");
+        let account_id = env::predecessor_account_id();
+        match self.storage_deposits.get(&account_id) {
+            Some(total) => {
+                assert!(
+                    self.supporters.get(&account_id).is_none(),
+                    "Can't unregister the account while supporting Kickstarters"
+                );
+                self.storage_deposits.remove(&account_id);
+                Promise::new(account_id.clone()).transfer(total);
+                log!("STORAGE: {} unregistered", account_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128::from(self.storage_cost(1)),
+            max: None,
+        }
+    }
+
+    fn storage_balance_of(&self, account_id: ValidAccountId) -> Option<StorageBalance> {
+        self.internal_storage_balance_of(account_id.as_ref())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+    use near_sdk::VMContext;
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::tests::unit_test_utils::*;
+
+    #[test]
+    fn test_storage_deposit_and_withdraw() {
+        let (mut context, mut contract) = contract_only_setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        assert!(contract.storage_balance_of(SUPPORTER_ACCOUNT.try_into().unwrap()).is_none());
+
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, min_balance + 10);
+        let storage_balance = contract.storage_deposit(None, None);
+        assert_eq!(storage_balance.total.0, min_balance + 10);
+        assert_eq!(storage_balance.available.0, min_balance + 10);
+
+        set_attached_deposit(&mut context, 1);
+        let storage_balance = contract.storage_withdraw(Some(U128::from(10)));
+        assert_eq!(storage_balance.total.0, min_balance);
+        assert!(contract.storage_unregister(None));
+        assert!(contract.storage_balance_of(SUPPORTER_ACCOUNT.try_into().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_storage_registration_only() {
+        let (mut context, mut contract) = contract_only_setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, 2 * min_balance);
+        let storage_balance = contract.storage_deposit(
+            Some(OTHER_SUPPORTER_ACCOUNT.try_into().unwrap()),
+            Some(true)
+        );
+        assert_eq!(storage_balance.total.0, min_balance);
+        assert!(contract.storage_balance_of(SUPPORTER_ACCOUNT.try_into().unwrap()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_storage_deposit_below_min() {
+        let (mut context, mut contract) = contract_only_setup();
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, 1);
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    fn test_missing_storage_for_new_supporter() {
+        let (_context, contract) = contract_only_setup();
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        assert_eq!(contract.internal_storage_used_by(&supporter_id), 0);
+        assert_eq!(contract.storage_cost(1), contract.storage_balance_bounds().min.0);
+    }
+
+    /// The longest account ids, so the measured storage is the worst case.
+    fn long_account_id(name: &str) -> String {
+        format!("{}{}", name, "x".repeat(64 - name.len()))
+    }
+
+    /// A funded Kickstarter with the max deposit tiers, time-weighted and with a referral fee.
+    fn new_kickstarter_with_all_entries(context: &mut VMContext, contract: &mut KatherineFundraising) -> KickstarterId {
+        set_predecessor(context, OWNER_ACCOUNT);
+        let kickstarter_id = _new_kickstarter(context.clone(), contract);
+        let mut kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        kickstarter.deposit_tiers = (1..=MAX_DEPOSIT_TIERS as u64)
+            .map(|day| DepositTier {
+                end_timestamp: kickstarter.open_timestamp + day * DAY_MILLIS,
+                multiplier: 15_000,
+            })
+            .collect();
+        kickstarter.time_weighted = true;
+        kickstarter.referral_fee_percent = 500;
+        contract.internal_save_kickstarter(&kickstarter);
+        _new_goal(contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(context, contract, kickstarter_id);
+        kickstarter_id
+    }
+
+    fn deposit_msg(kickstarter_id: KickstarterId, key: &str, account_id: &str) -> String {
+        format!(r#"{{"kickstarter_id":{},"{}":"{}"}}"#, kickstarter_id, key, account_id)
+    }
+
+    #[test]
+    fn test_measured_storage() {
+        let (mut context, mut contract) = contract_only_setup();
+        let first_kickstarter_id = new_kickstarter_with_all_entries(&mut context, &mut contract);
+        let second_kickstarter_id = new_kickstarter_with_all_entries(&mut context, &mut contract);
+        let supporter_id = long_account_id("supporter");
+        let depositor_id = long_account_id("depositor");
+        let referrer_id = long_account_id("referrer");
+
+        // The storage usage is reset by each testing_env, so everything runs in a single one.
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        set_predecessor(&mut context, METAPOOL_CONTRACT_ADDRESS);
+        set_attached_deposit(&mut context, ntoy(1));
+
+        let storage_before = env::storage_usage();
+        contract.storage_deposit(Some(supporter_id.clone().try_into().unwrap()), None);
+        contract.ft_on_transfer(
+            supporter_id.clone().try_into().unwrap(),
+            U128::from(ntoy(10)),
+            deposit_msg(first_kickstarter_id, "referrer", &referrer_id),
+        );
+        let first_deposit = env::storage_usage() - storage_before;
+
+        let storage_before = env::storage_usage();
+        contract.ft_on_transfer(
+            supporter_id.clone().try_into().unwrap(),
+            U128::from(ntoy(10)),
+            deposit_msg(second_kickstarter_id, "referrer", &referrer_id),
+        );
+        let second_deposit = env::storage_usage() - storage_before;
+
+        contract.storage_deposit(Some(depositor_id.clone().try_into().unwrap()), None);
+        let storage_before = env::storage_usage();
+        contract.ft_on_transfer(
+            depositor_id.clone().try_into().unwrap(),
+            U128::from(ntoy(10)),
+            deposit_msg(first_kickstarter_id, "beneficiary", &supporter_id),
+        );
+        let deposit_on_behalf = env::storage_usage() - storage_before;
+
+        assert!(
+            first_deposit <= SUPPORTER_STORAGE_BYTES + SUPPORTED_PROJECT_STORAGE_BYTES,
+            "first deposit used {} bytes",
+            first_deposit
+        );
+        assert!(
+            second_deposit <= SUPPORTED_PROJECT_STORAGE_BYTES,
+            "second deposit used {} bytes",
+            second_deposit
+        );
+        assert!(
+            deposit_on_behalf <= DEPOSIT_ON_BEHALF_STORAGE_BYTES,
+            "deposit on behalf used {} bytes",
+            deposit_on_behalf
+        );
+    }
+}
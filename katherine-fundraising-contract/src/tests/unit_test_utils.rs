@@ -3,23 +3,44 @@
 #![allow(dead_code)]
 
 use super::*;
-use near_sdk::json_types::Base58PublicKey;
-use near_sdk::{AccountId, MockedBlockchain, PromiseResult, VMContext};
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_contract_standards::storage_management::StorageManagement;
+use near_sdk::json_types::{Base58PublicKey, ValidAccountId, U128};
+use near_sdk::{testing_env, AccountId, MockedBlockchain, PromiseResult, VMContext};
+use std::convert::TryInto;
 
 /// Tests constants
 pub const SYSTEM_ACCOUNT: &'static str = "system";
 pub const CONTRACT_ACCOUNT: &'static str = "contract";
 pub const OWNER_ACCOUNT: &'static str = SYSTEM_ACCOUNT;
 pub const SUPPORTER_ACCOUNT: &'static str = "owner";
+pub const OTHER_SUPPORTER_ACCOUNT: &'static str = "supporter";
+pub const REFERRER_ACCOUNT: &'static str = "referrer";
+pub const OPERATOR_ACCOUNT: &'static str = "operator";
+pub const KEEPER_ACCOUNT: &'static str = "keeper";
+pub const KICKSTARTER_OWNER_ACCOUNT: &'static str = "kickstarter_owner";
+pub const TOKEN_CONTRACT_ACCOUNT: &'static str = "ptoken";
 pub const SUPPORTER_ID: usize = 0;
 pub const STAKING_GOAL: u128 = 1000;
 pub const TEST_INITIAL_BALANCE: u128 = 100;
 pub const DEPOSIT_AMOUNT: u128 = 200;
+pub const MIN_DEPOSIT_AMOUNT: u128 = 2;
+pub const KATHERINE_FEE_PERCENT: BasisPoints = 100;
 pub const START_TIME_IN_DAYS: u64 = 1777;
 pub const KICKSTARTER_NAME: &'static str = "test_kickstarter";
 pub const KICKSTARTER_SLUG: &'static str = "test_kickstarter_slug";
 pub const METAPOOL_CONTRACT_ADDRESS: &'static str = "meta-v2.pool.testnet";
 
+/// Kickstarter timeline, the funding period opens the day after the start.
+pub const OPEN_TIME_IN_DAYS: u64 = START_TIME_IN_DAYS + 1;
+pub const CLOSE_TIME_IN_DAYS: u64 = START_TIME_IN_DAYS + 31;
+pub const CLIFF_TIME_IN_DAYS: u64 = CLOSE_TIME_IN_DAYS + 10;
+pub const UNFREEZE_TIME_IN_DAYS: u64 = CLOSE_TIME_IN_DAYS + 30;
+pub const END_TIME_IN_DAYS: u64 = CLOSE_TIME_IN_DAYS + 60;
+pub const DAY_MILLIS: EpochMillis = 86_400_000;
+pub const DEPOSITS_HARD_CAP: u128 = 1_000;
+pub const MAX_TOKENS_TO_RELEASE_PER_STNEAR: u128 = 2;
+
 /// Get VMContext for Unit tests
 pub fn get_context(
     predecessor_account_id: AccountId,
@@ -48,6 +69,76 @@ pub fn get_context(
     }
 }
 
+/// Get initial context for tests
+pub fn basic_context() -> VMContext {
+    get_context(
+        SYSTEM_ACCOUNT.into(),
+        ntoy(TEST_INITIAL_BALANCE),
+        0,
+        to_ts(START_TIME_IN_DAYS),
+        false,
+    )
+}
+
+/// Creates a new contract
+pub fn new_contract() -> KatherineFundraising {
+    KatherineFundraising::new(
+        OWNER_ACCOUNT.into(),
+        U128::from(MIN_DEPOSIT_AMOUNT),
+        METAPOOL_CONTRACT_ADDRESS.to_string(),
+        KATHERINE_FEE_PERCENT,
+    )
+}
+
+pub fn contract_only_setup() -> (VMContext, KatherineFundraising) {
+    let context = basic_context();
+    testing_env!(context.clone());
+    let contract = new_contract();
+    return (context, contract);
+}
+
+/// Creates a new contract with a single Kickstarter, for the Kickstarter accounting tests.
+pub fn kickstarter_only_setup() -> (VMContext, KatherineFundraising, Kickstarter) {
+    let (context, mut contract) = contract_only_setup();
+    let kickstarter_id = _new_kickstarter(context.clone(), &mut contract);
+    let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+    (context, contract, kickstarter)
+}
+
+/// Sets the predecessor and the signer of the next calls.
+pub fn set_predecessor(context: &mut VMContext, account_id: &str) {
+    context.predecessor_account_id = account_id.into();
+    context.signer_account_id = account_id.into();
+    testing_env!(context.clone());
+}
+
+/// Sets the block timestamp of the next calls, in millis like the Kickstarter timestamps.
+pub fn set_timestamp(context: &mut VMContext, timestamp: EpochMillis) {
+    context.block_timestamp = timestamp * 1_000_000;
+    testing_env!(context.clone());
+}
+
+/// Sets the result of the promise that the next callback receives.
+pub fn set_promise_result(context: &VMContext, result: PromiseResult) {
+    testing_env!(
+        context.clone(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        vec![result]
+    );
+}
+
+/// Successful result of the Meta Pool stNEAR price call.
+pub fn st_near_price_result(st_near_price: Balance) -> PromiseResult {
+    PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128::from(st_near_price)).unwrap())
+}
+
+pub fn set_attached_deposit(context: &mut VMContext, amount: Balance) {
+    context.attached_deposit = amount;
+    testing_env!(context.clone());
+}
+
 /// Convert near to yocto
 pub fn ntoy(near_amount: u128) -> u128 {
     return near_amount * 10u128.pow(24);
@@ -64,16 +155,100 @@ pub fn to_nanos(num_days: u64) -> u64 {
     return num_days * 86400_000_000_000;
 }
 
+/// Convert to Timestamp in millis
+pub fn to_millis(num_days: u64) -> EpochMillis {
+    to_ts(num_days) / 1_000_000
+}
+
+/// Creates a Kickstarter with a unique slug, the predecessor must be an operator.
 pub fn _new_kickstarter(
     _context: VMContext,
     contract: &mut KatherineFundraising,
 ) -> KickstarterIdJSON {
     contract.create_kickstarter(
         KICKSTARTER_NAME.into(),
-        KICKSTARTER_SLUG.into(),
-        OWNER_ACCOUNT.into(),
-        to_ts(START_TIME_IN_DAYS),      // open_timestamp
-        to_ts(START_TIME_IN_DAYS * 50), // close_timestamp
-        CONTRACT_ACCOUNT.into(),        // token_contract_address
+        format!("{}_{}", KICKSTARTER_SLUG, contract.get_total_kickstarters()),
+        KICKSTARTER_OWNER_ACCOUNT.into(),
+        to_millis(OPEN_TIME_IN_DAYS),
+        to_millis(CLOSE_TIME_IN_DAYS),
+        TOKEN_CONTRACT_ACCOUNT.into(),
+        U128::from(ntoy(DEPOSITS_HARD_CAP)),
+        U128::from(ntoy(MAX_TOKENS_TO_RELEASE_PER_STNEAR)),
+        24,
     )
 }
+
+/// Adds a goal to the Kickstarter, the predecessor must be an operator or the Kickstarter owner.
+pub fn _new_goal(
+    contract: &mut KatherineFundraising,
+    kickstarter_id: KickstarterId,
+    desired_amount: Balance,
+    tokens_to_release_per_stnear: Balance,
+) -> GoalId {
+    contract.create_goal(
+        kickstarter_id,
+        "test_goal".to_string(),
+        U128::from(desired_amount),
+        to_millis(UNFREEZE_TIME_IN_DAYS),
+        U128::from(tokens_to_release_per_stnear),
+        to_millis(CLIFF_TIME_IN_DAYS),
+        to_millis(END_TIME_IN_DAYS),
+        None,
+        None,
+        None,
+    )
+}
+
+/// The Kickstarter owner covers the required reward tokens, so Supporters could deposit.
+pub fn _fund_kickstarter(
+    context: &mut VMContext,
+    contract: &mut KatherineFundraising,
+    kickstarter_id: KickstarterId,
+) {
+    let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+    let amount = contract.calculate_min_tokens_to_allow_support(&kickstarter);
+    set_predecessor(context, &kickstarter.token_contract_address);
+    contract.ft_on_transfer(
+        KICKSTARTER_OWNER_ACCOUNT.try_into().unwrap(),
+        U128::from(amount),
+        kickstarter_id.to_string(),
+    );
+}
+
+/// The Supporter covers its storage, and deposits the stNEAR through Meta Pool.
+pub fn _supporter_deposit(
+    context: &mut VMContext,
+    contract: &mut KatherineFundraising,
+    kickstarter_id: KickstarterId,
+    supporter_id: &str,
+    amount: Balance,
+) {
+    let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+    let missing_storage = contract.internal_missing_storage(&supporter_id.to_string(), &kickstarter);
+    if missing_storage > 0 {
+        set_predecessor(context, supporter_id);
+        set_attached_deposit(context, missing_storage);
+        contract.storage_deposit(None, None);
+        set_attached_deposit(context, 0);
+    }
+    set_predecessor(context, METAPOOL_CONTRACT_ADDRESS);
+    contract.ft_on_transfer(
+        supporter_id.try_into().unwrap(),
+        U128::from(amount),
+        kickstarter_id.to_string(),
+    );
+}
+
+/// Activates the Kickstarter after the funding period, as the keeper would with the stNEAR price.
+pub fn _activate_kickstarter(
+    context: &mut VMContext,
+    contract: &mut KatherineFundraising,
+    kickstarter_id: KickstarterId,
+) {
+    set_timestamp(context, to_millis(CLOSE_TIME_IN_DAYS) + 1);
+    let mut kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+    let goal = kickstarter.get_achieved_goal().expect("The Kickstarter did not achieve any goal");
+    contract
+        .internal_activate_successful_kickstarter(&mut kickstarter, goal.id, NEAR, &KEEPER_ACCOUNT.to_string())
+        .unwrap();
+}
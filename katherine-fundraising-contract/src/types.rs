@@ -4,6 +4,10 @@ use near_sdk::json_types::{ValidAccountId, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::AccountId;
 
+use crate::kickstarter::{DepositTier, RewardMode};
+use crate::metadata::KickstarterMetadata;
+use crate::vesting::VestingSchedule;
+
 pub type BalanceJSON = U128;
 
 pub type KickstarterId = u32;
@@ -21,6 +25,72 @@ construct_uint! {
     pub struct U256(4);
 }
 
+/// Roles that the Katherine owner could grant to other accounts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// Creates and updates the Kickstarters and their goals.
+    Operator,
+    /// Withdraws the Katherine fee.
+    FeeCollector,
+    /// Processes and unfreezes the Kickstarters.
+    Robot,
+}
+
+/// Explicit kind of an `ft_transfer_call` deposit to Katherine.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum DepositAction {
+    /// A Supporter deposit in stNEAR.
+    Deposit,
+    /// A Kickstarter deposit of the reward tokens.
+    RewardTokens,
+}
+
+/// The `msg` of an `ft_transfer_call` to Katherine. A bare KickstarterId is still accepted.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(deny_unknown_fields)]
+pub struct DepositMsg {
+    pub version: Option<u8>,
+    pub kickstarter_id: KickstarterIdJSON,
+    /// If missing, the action is taken from the deposited token.
+    pub action: Option<DepositAction>,
+    /// Only for the first deposit of the Supporter in the Kickstarter.
+    pub referrer: Option<ValidAccountId>,
+    /// The Supporter that owns the deposit, the sender by default.
+    pub beneficiary: Option<ValidAccountId>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardFunderJSON {
+    pub funder_id: AccountId,
+    pub amount: BalanceJSON,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositOnBehalfJSON {
+    pub depositor_id: AccountId,
+    pub beneficiary_id: SupporterId,
+    pub amount: BalanceJSON,
+}
+
+/// The weighted deposits removed by a withdraw during the funding period. They are passed
+/// to the transfer callback, to restore them if the transfer fails.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawnDepositsJSON {
+    /// Removed from each tier, empty if the Kickstarter has no deposit tiers.
+    pub tier_deposits: Vec<BalanceJSON>,
+    /// Zero if the Kickstarter is not time-weighted.
+    pub time_weighted_deposit: BalanceJSON,
+    /// Removed from the referrer of the Supporter.
+    pub referred_deposit: BalanceJSON,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct KickstarterJSON {
@@ -47,9 +117,71 @@ pub struct KickstarterDetailsJSON {
     pub goals: Vec<GoalJSON>,
     pub active: bool,
     pub successful: Option<bool>,
+    pub cancelled: bool,
+    pub cancel_reason: Option<String>,
     pub winner_goal_id: Option<u8>,
     pub enough_reward_tokens: bool,
     pub available_reward_tokens: BalanceJSON,
+    pub katherine_fee_percent: BasisPoints,
+    pub deposits_paused: bool,
+    pub outflows_paused: bool,
+    pub referral_fee_percent: BasisPoints,
+    pub total_referred: BalanceJSON,
+    pub deposit_tiers: Vec<DepositTier>,
+    pub reward_mode: RewardMode,
+    pub keeper_bounty: BalanceJSON,
+    pub time_weighted: bool,
+    pub effective_tokens_to_release_per_stnear: Option<BalanceJSON>,
+    pub effective_unfreeze_timestamp: Option<EpochMillis>,
+    pub metadata: Option<KickstarterMetadata>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractParamsJSON {
+    pub min_deposit_amount: BalanceJSON,
+    pub metapool_contract_address: AccountId,
+    pub katherine_fee_percent: BasisPoints,
+    pub max_goals_per_kickstarter: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PausedStatusJSON {
+    pub deposits_paused: bool,
+    pub outflows_paused: bool,
+}
+
+/// Kickstarter terms for the **validate_kickstarter_config** view.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct KickstarterConfigJSON {
+    pub open_timestamp: EpochMillis,
+    pub close_timestamp: EpochMillis,
+    pub deposits_hard_cap: BalanceJSON,
+    pub max_tokens_to_release_per_stnear: BalanceJSON,
+    #[serde(default)]
+    pub deposit_tiers: Vec<DepositTier>,
+    #[serde(default)]
+    pub goals: Vec<GoalInput>,
+}
+
+/// Goal terms sent by the Kickstarter, the id is the position in the list.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GoalInput {
+    pub name: String,
+    pub desired_amount: BalanceJSON,
+    pub unfreeze_timestamp: EpochMillis,
+    pub tokens_to_release_per_stnear: BalanceJSON,
+    pub cliff_timestamp: EpochMillis,
+    pub end_timestamp: EpochMillis,
+    #[serde(default)]
+    pub min_supporters: u32,
+    #[serde(default)]
+    pub max_supporter_share: Option<BasisPoints>,
+    #[serde(default)]
+    pub vesting_schedule: VestingSchedule,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -62,6 +194,17 @@ pub struct GoalJSON {
     pub tokens_to_release_per_stnear: BalanceJSON,
     pub cliff_timestamp: EpochMillis,
     pub end_timestamp: EpochMillis,
+    pub min_supporters: u32,
+    pub max_supporter_share: Option<BasisPoints>,
+    pub vesting_schedule: VestingSchedule,
+}
+
+/// Rewards unlocked up to the timestamp.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingUnlockJSON {
+    pub timestamp: EpochMillis,
+    pub unlocked: BalanceJSON,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -71,6 +214,49 @@ pub struct KickstarterStatusJSON {
     pub unsuccessful: Vec<KickstarterIdJSON>,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BatchStatus {
+    Successful,
+    Failed,
+    Skipped,
+}
+
+/// Result of a Kickstarter in the robot batches, the message explains a failed or skipped one.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BatchResultJSON {
+    pub kickstarter_id: KickstarterIdJSON,
+    pub status: BatchStatus,
+    pub message: Option<String>,
+}
+
+impl BatchResultJSON {
+    pub fn successful(kickstarter_id: KickstarterIdJSON) -> Self {
+        BatchResultJSON {
+            kickstarter_id,
+            status: BatchStatus::Successful,
+            message: None,
+        }
+    }
+
+    pub fn failed(kickstarter_id: KickstarterIdJSON, message: &str) -> Self {
+        BatchResultJSON {
+            kickstarter_id,
+            status: BatchStatus::Failed,
+            message: Some(message.to_string()),
+        }
+    }
+
+    pub fn skipped(kickstarter_id: KickstarterIdJSON, message: &str) -> Self {
+        BatchResultJSON {
+            kickstarter_id,
+            status: BatchStatus::Skipped,
+            message: Some(message.to_string()),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct ActiveKickstarterJSON {
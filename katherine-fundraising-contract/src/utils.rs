@@ -23,41 +23,3 @@ pub fn get_current_epoch_millis() -> EpochMillis {
 pub fn proportional(amount: u128, numerator: u128, denominator: u128) -> u128 {
     return (U256::from(amount) * U256::from(numerator) / U256::from(denominator)).as_u128();
 }
-
-/// DEPRECATED: fn to calculate the linear release with steps. But the release will be full linear.
-#[allow(dead_code)]
-fn proportional_with_steps(
-    amount: Balance,
-    numerator: u128,
-    denominator: u128,
-    steps: u128,
-) -> Balance {
-    let mut amount_to_release: Balance = 0;
-    let result = proportional(amount, numerator, denominator);
-    for index in 1..steps {
-        let proportion = proportional(amount, index, steps);
-        if proportion <= result {
-            amount_to_release = proportion;
-        } else {
-            break;
-        }
-    }
-    amount_to_release
-}
-
-pub fn get_linear_release_proportion(
-    amount: Balance,
-    cliff_timestamp: EpochMillis,
-    end_timestamp: EpochMillis,
-) -> Balance {
-    let now = get_current_epoch_millis();
-    if now < cliff_timestamp {
-        0
-    } else if now >= end_timestamp {
-        amount
-    } else {
-        let numerator = now as u128 - cliff_timestamp as u128;
-        let denominator = end_timestamp as u128 - cliff_timestamp as u128;
-        proportional(amount, numerator, denominator)
-    }
-}
@@ -0,0 +1,235 @@
+use crate::*;
+use near_sdk::near_bindgen;
+
+/// The Kickstarter terms are checked together, the same routine is used for the creation,
+/// the updates, the goal changes and the **validate_kickstarter_config** view.
+
+pub(crate) struct KickstarterConfig {
+    pub open_timestamp: EpochMillis,
+    pub close_timestamp: EpochMillis,
+    pub deposits_hard_cap: Balance,
+    pub max_tokens_to_release_per_stnear: Balance,
+    pub deposit_tiers: Vec<DepositTier>,
+    pub goals: Vec<Goal>,
+}
+
+impl KickstarterConfig {
+    fn from_json(config: KickstarterConfigJSON) -> Self {
+        KickstarterConfig {
+            open_timestamp: config.open_timestamp,
+            close_timestamp: config.close_timestamp,
+            deposits_hard_cap: Balance::from(config.deposits_hard_cap),
+            max_tokens_to_release_per_stnear: Balance::from(config.max_tokens_to_release_per_stnear),
+            deposit_tiers: config.deposit_tiers,
+            goals: config
+                .goals
+                .into_iter()
+                .enumerate()
+                .map(|(id, input)| Goal::from_input(id as GoalId, input))
+                .collect(),
+        }
+    }
+}
+
+impl Kickstarter {
+    pub(crate) fn to_config(&self) -> KickstarterConfig {
+        KickstarterConfig {
+            open_timestamp: self.open_timestamp,
+            close_timestamp: self.close_timestamp,
+            deposits_hard_cap: self.deposits_hard_cap,
+            max_tokens_to_release_per_stnear: self.max_tokens_to_release_per_stnear,
+            deposit_tiers: self.deposit_tiers.clone(),
+            goals: self.goals.iter().collect(),
+        }
+    }
+}
+
+impl KatherineFundraising {
+    /// Returns all the errors found in the config, an empty list means a valid config.
+    pub(crate) fn validate_config(&self, config: &KickstarterConfig) -> Vec<String> {
+        let mut errors: Vec<String> = Vec::new();
+        if config.open_timestamp < get_current_epoch_millis() {
+            errors.push("Incorrect open timestamp!".to_string());
+        }
+        if config.close_timestamp < config.open_timestamp {
+            errors.push("Incorrect close timestamp!".to_string());
+        }
+
+        if config.deposit_tiers.len() > MAX_DEPOSIT_TIERS {
+            errors.push(format!("Too many deposit tiers, max number is {}", MAX_DEPOSIT_TIERS));
+        }
+        let mut previous_end = config.open_timestamp;
+        for (index, tier) in config.deposit_tiers.iter().enumerate() {
+            if tier.end_timestamp <= previous_end || tier.end_timestamp > config.close_timestamp {
+                errors.push(format!(
+                    "Deposit tier {}: deposit tiers must end in order within the funding period!",
+                    index
+                ));
+            }
+            if (tier.multiplier as u128) < BASIS_POINTS || tier.multiplier > MAX_DEPOSIT_TIER_MULTIPLIER {
+                errors.push(format!(
+                    "Deposit tier {}: multiplier must be between {} and {} basis points",
+                    index, BASIS_POINTS, MAX_DEPOSIT_TIER_MULTIPLIER
+                ));
+            }
+            previous_end = tier.end_timestamp;
+        }
+
+        if config.goals.len() > self.max_goals_per_kickstarter as usize {
+            errors.push(format!("Too many goals, max number is {}", self.max_goals_per_kickstarter));
+        }
+        for (index, goal) in config.goals.iter().enumerate() {
+            if goal.desired_amount > config.deposits_hard_cap {
+                errors.push(format!(
+                    "Goal {}: desired amount must not exceed the deposits hard cap!",
+                    index
+                ));
+            }
+            if goal.tokens_to_release_per_stnear > config.max_tokens_to_release_per_stnear {
+                errors.push(format!(
+                    "Goal {}: tokens to release must not exceed the max tokens to release per stNEAR!",
+                    index
+                ));
+            }
+            if goal.unfreeze_timestamp < config.close_timestamp {
+                errors.push(format!(
+                    "Goal {}: funds cannot be unfreezed before the funding period ends!",
+                    index
+                ));
+            }
+            if goal.cliff_timestamp < config.close_timestamp {
+                errors.push(format!(
+                    "Goal {}: the cliff cannot start before the funding period ends!",
+                    index
+                ));
+            }
+            if let Some(max_supporter_share) = goal.max_supporter_share {
+                if max_supporter_share == 0 || max_supporter_share as u128 > BASIS_POINTS {
+                    errors.push(format!(
+                        "Goal {}: max supporter share must be between 1 and {} basis points",
+                        index, BASIS_POINTS
+                    ));
+                }
+            }
+            if goal.cliff_timestamp > goal.end_timestamp {
+                errors.push(format!(
+                    "Goal {}: the cliff cannot be after the end of the rewards release!",
+                    index
+                ));
+            }
+            if let Err(error) = goal
+                .vesting_schedule
+                .validate(goal.cliff_timestamp, goal.end_timestamp)
+            {
+                errors.push(format!("Goal {}: {}", index, error));
+            }
+            if index > 0 {
+                let last_goal = &config.goals[index - 1];
+                if goal.desired_amount < last_goal.desired_amount {
+                    errors.push(format!(
+                        "Goal {}: next goal cannot have a lower desired amount that the last goal!",
+                        index
+                    ));
+                }
+                if goal.unfreeze_timestamp > last_goal.unfreeze_timestamp {
+                    errors.push(format!(
+                        "Goal {}: next goal cannot freeze supporter funds any longer than the last goal!",
+                        index
+                    ));
+                }
+                if goal.tokens_to_release_per_stnear < last_goal.tokens_to_release_per_stnear {
+                    errors.push(format!(
+                        "Goal {}: next goal cannot release less pTOKEN than the last goal!",
+                        index
+                    ));
+                }
+            }
+        }
+        errors
+    }
+
+    pub(crate) fn assert_valid_config(&self, config: &KickstarterConfig) {
+        let errors = self.validate_config(config);
+        if !errors.is_empty() {
+            panic!("Invalid Kickstarter config: {}", errors.join(" "));
+        }
+    }
+}
+
+#[near_bindgen]
+impl KatherineFundraising {
+    /// Checks a config before creating or updating a Kickstarter.
+    pub fn validate_kickstarter_config(&self, config: KickstarterConfigJSON) -> Vec<String> {
+        self.validate_config(&KickstarterConfig::from_json(config))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::json_types::U128;
+
+    use super::*;
+    use crate::tests::unit_test_utils::*;
+
+    fn open_timestamp() -> EpochMillis {
+        to_millis(OPEN_TIME_IN_DAYS)
+    }
+
+    fn close_timestamp() -> EpochMillis {
+        to_millis(CLOSE_TIME_IN_DAYS)
+    }
+
+    fn goal(desired_amount: Balance, unfreeze_timestamp: EpochMillis, cliff_timestamp: EpochMillis) -> GoalInput {
+        GoalInput {
+            name: "goal".to_string(),
+            desired_amount: U128::from(desired_amount),
+            unfreeze_timestamp,
+            tokens_to_release_per_stnear: U128::from(NEAR),
+            cliff_timestamp,
+            end_timestamp: close_timestamp() + 10_000,
+            min_supporters: 0,
+            max_supporter_share: None,
+            vesting_schedule: VestingSchedule::default(),
+        }
+    }
+
+    fn config(goals: Vec<GoalInput>) -> KickstarterConfigJSON {
+        KickstarterConfigJSON {
+            open_timestamp: open_timestamp(),
+            close_timestamp: close_timestamp(),
+            deposits_hard_cap: U128::from(100 * NEAR),
+            max_tokens_to_release_per_stnear: U128::from(2 * NEAR),
+            deposit_tiers: Vec::new(),
+            goals,
+        }
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let (_context, contract) = contract_only_setup();
+        let errors = contract.validate_kickstarter_config(config(vec![
+            goal(10 * NEAR, close_timestamp() + 2_000, close_timestamp()),
+            goal(20 * NEAR, close_timestamp() + 1_000, close_timestamp()),
+        ]));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_invalid_goal_timeline() {
+        let (_context, contract) = contract_only_setup();
+        let mut bad_goal = goal(10 * NEAR, close_timestamp() - 1, close_timestamp() + 20_000);
+        bad_goal.desired_amount = U128::from(200 * NEAR);
+        let errors = contract.validate_kickstarter_config(config(vec![bad_goal]));
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_invalid_goal_ladder() {
+        let (_context, contract) = contract_only_setup();
+        let errors = contract.validate_kickstarter_config(config(vec![
+            goal(20 * NEAR, close_timestamp() + 1_000, close_timestamp()),
+            goal(10 * NEAR, close_timestamp() + 2_000, close_timestamp()),
+        ]));
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Unlocked share of the rewards at a timestamp, in basis points.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingPoint {
+    pub timestamp: EpochMillis,
+    pub unlocked: BasisPoints,
+}
+
+/// Release of the Supporter rewards for a Goal. Nothing is released before the cliff, and
+/// everything is released at the end.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VestingSchedule {
+    /// Initial unlock at the cliff, the rest is released linearly until the end.
+    Linear { initial_unlock: BasisPoints },
+    /// Initial unlock at the cliff, the rest is released in equal steps every interval.
+    Steps {
+        initial_unlock: BasisPoints,
+        interval: EpochMillis,
+    },
+    /// Piecewise-linear curve between the points, the last point must unlock everything.
+    Custom { points: Vec<VestingPoint> },
+}
+
+impl Default for VestingSchedule {
+    fn default() -> Self {
+        VestingSchedule::Linear { initial_unlock: 0 }
+    }
+}
+
+impl VestingSchedule {
+    /// Released amount at the timestamp.
+    pub fn get_released(
+        &self,
+        amount: Balance,
+        cliff_timestamp: EpochMillis,
+        end_timestamp: EpochMillis,
+        timestamp: EpochMillis,
+    ) -> Balance {
+        if timestamp < cliff_timestamp {
+            return 0;
+        }
+        if timestamp >= end_timestamp {
+            return amount;
+        }
+        match self {
+            VestingSchedule::Linear { initial_unlock } => {
+                let initial = proportional(amount, *initial_unlock as u128, BASIS_POINTS);
+                initial
+                    + proportional(
+                        amount - initial,
+                        (timestamp - cliff_timestamp) as u128,
+                        (end_timestamp - cliff_timestamp) as u128,
+                    )
+            }
+            VestingSchedule::Steps {
+                initial_unlock,
+                interval,
+            } => {
+                let initial = proportional(amount, *initial_unlock as u128, BASIS_POINTS);
+                let steps = Self::get_number_of_steps(cliff_timestamp, end_timestamp, *interval);
+                let steps_done = (timestamp - cliff_timestamp) / interval;
+                initial + proportional(amount - initial, steps_done as u128, steps as u128)
+            }
+            VestingSchedule::Custom { points } => {
+                let mut released: Balance = 0;
+                let mut previous: Option<&VestingPoint> = None;
+                for point in points.iter() {
+                    if timestamp < point.timestamp {
+                        if let Some(previous) = previous {
+                            let unlocked = previous.unlocked as u128
+                                + proportional(
+                                    (point.unlocked - previous.unlocked) as u128,
+                                    (timestamp - previous.timestamp) as u128,
+                                    (point.timestamp - previous.timestamp) as u128,
+                                );
+                            released = proportional(amount, unlocked, BASIS_POINTS);
+                        }
+                        break;
+                    }
+                    released = proportional(amount, point.unlocked as u128, BASIS_POINTS);
+                    previous = Some(point);
+                }
+                released
+            }
+        }
+    }
+
+    /// The last step could be shorter than the interval, it ends at the end timestamp.
+    fn get_number_of_steps(cliff_timestamp: EpochMillis, end_timestamp: EpochMillis, interval: EpochMillis) -> u64 {
+        let period = end_timestamp - cliff_timestamp;
+        period / interval + if period % interval > 0 { 1 } else { 0 }
+    }
+
+    /// Timestamps where the released amount changes its trend, to show the unlock timeline.
+    pub fn get_timeline(&self, cliff_timestamp: EpochMillis, end_timestamp: EpochMillis) -> Vec<EpochMillis> {
+        let mut timeline: Vec<EpochMillis> = vec![cliff_timestamp];
+        match self {
+            VestingSchedule::Linear { .. } => {}
+            VestingSchedule::Steps { interval, .. } => {
+                let mut timestamp = cliff_timestamp.saturating_add(*interval);
+                while timestamp < end_timestamp {
+                    timeline.push(timestamp);
+                    timestamp = timestamp.saturating_add(*interval);
+                }
+            }
+            VestingSchedule::Custom { points } => {
+                for point in points.iter() {
+                    if point.timestamp > cliff_timestamp && point.timestamp < end_timestamp {
+                        timeline.push(point.timestamp);
+                    }
+                }
+            }
+        }
+        if end_timestamp > cliff_timestamp {
+            timeline.push(end_timestamp);
+        }
+        timeline
+    }
+
+    pub(crate) fn validate(&self, cliff_timestamp: EpochMillis, end_timestamp: EpochMillis) -> Result<(), String> {
+        match self {
+            VestingSchedule::Linear { initial_unlock } => Self::validate_initial_unlock(*initial_unlock),
+            VestingSchedule::Steps {
+                initial_unlock,
+                interval,
+            } => {
+                Self::validate_initial_unlock(*initial_unlock)?;
+                if *interval == 0 {
+                    return Err("the vesting interval must be greater than 0".to_string());
+                }
+                let period = end_timestamp.saturating_sub(cliff_timestamp);
+                if Self::get_number_of_steps(0, period, *interval) > MAX_VESTING_STEPS {
+                    return Err(format!("too many vesting steps, max number is {}", MAX_VESTING_STEPS));
+                }
+                Ok(())
+            }
+            VestingSchedule::Custom { points } => {
+                if points.is_empty() || points.len() > MAX_VESTING_STEPS as usize {
+                    return Err(format!(
+                        "the vesting curve must have between 1 and {} points",
+                        MAX_VESTING_STEPS
+                    ));
+                }
+                let mut previous: Option<&VestingPoint> = None;
+                for point in points.iter() {
+                    if point.timestamp < cliff_timestamp || point.timestamp > end_timestamp {
+                        return Err("the vesting points must be between the cliff and the end".to_string());
+                    }
+                    if let Some(previous) = previous {
+                        if point.timestamp <= previous.timestamp || point.unlocked < previous.unlocked {
+                            return Err("the vesting points must be in order".to_string());
+                        }
+                    }
+                    previous = Some(point);
+                }
+                if points.last().unwrap().unlocked as u128 != BASIS_POINTS {
+                    return Err(format!("the last vesting point must unlock {} basis points", BASIS_POINTS));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn validate_initial_unlock(initial_unlock: BasisPoints) -> Result<(), String> {
+        if initial_unlock as u128 > BASIS_POINTS {
+            return Err(format!("the initial unlock cannot exceed {} basis points", BASIS_POINTS));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    const CLIFF: EpochMillis = 1_000;
+    const END: EpochMillis = 2_000;
+
+    #[test]
+    fn test_linear_with_initial_unlock() {
+        let schedule = VestingSchedule::Linear { initial_unlock: 1_000 };
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, CLIFF - 1), 0);
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, CLIFF), NEAR / 10);
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, 1_500), NEAR / 10 + 45 * NEAR / 100);
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, END), NEAR);
+        assert!(schedule.validate(CLIFF, END).is_ok());
+    }
+
+    #[test]
+    fn test_steps() {
+        let schedule = VestingSchedule::Steps {
+            initial_unlock: 0,
+            interval: 300,
+        };
+        // 4 steps, the last one is shorter.
+        assert_eq!(schedule.get_released(4 * NEAR, CLIFF, END, CLIFF + 299), 0);
+        assert_eq!(schedule.get_released(4 * NEAR, CLIFF, END, CLIFF + 300), NEAR);
+        assert_eq!(schedule.get_released(4 * NEAR, CLIFF, END, CLIFF + 900), 3 * NEAR);
+        assert_eq!(schedule.get_released(4 * NEAR, CLIFF, END, END), 4 * NEAR);
+        assert_eq!(schedule.get_timeline(CLIFF, END), vec![CLIFF, 1_300, 1_600, 1_900, END]);
+    }
+
+    #[test]
+    fn test_custom_curve() {
+        let schedule = VestingSchedule::Custom {
+            points: vec![
+                VestingPoint { timestamp: CLIFF, unlocked: 2_000 },
+                VestingPoint { timestamp: 1_500, unlocked: 6_000 },
+                VestingPoint { timestamp: END, unlocked: 10_000 },
+            ],
+        };
+        assert!(schedule.validate(CLIFF, END).is_ok());
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, CLIFF), NEAR / 5);
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, 1_250), 2 * NEAR / 5);
+        assert_eq!(schedule.get_released(NEAR, CLIFF, END, 1_500), 3 * NEAR / 5);
+        assert_eq!(schedule.get_timeline(CLIFF, END), vec![CLIFF, 1_500, END]);
+    }
+
+    #[test]
+    fn test_invalid_custom_curve() {
+        let schedule = VestingSchedule::Custom {
+            points: vec![VestingPoint { timestamp: 1_500, unlocked: 5_000 }],
+        };
+        assert!(schedule.validate(CLIFF, END).is_err());
+    }
+}
@@ -18,17 +18,9 @@ impl KatherineFundraising {
         supporter_id: SupporterId
     ) {
         let deposit = kickstarter.get_deposit(&supporter_id);
-        assert!(requested_amount <= deposit, "Not available amount!");
-        
-        // Ensure that the min_deposit_amount remains after a withdraw.
-        let amount_to_withdraw = if is_close(requested_amount, deposit) 
-                || (deposit - requested_amount) < self.min_deposit_amount {
-            deposit
-        } else {
-            requested_amount
-        };
+        let amount_to_withdraw = self.get_amount_to_withdraw_before_freeze(requested_amount, deposit);
 
-        self.supporter_withdraw_before_freeze(
+        let withdrawn = self.supporter_withdraw_before_freeze(
             amount_to_withdraw,
             deposit,
             kickstarter,
@@ -47,6 +39,7 @@ impl KatherineFundraising {
                 supporter_id,
                 kickstarter.id.into(),
                 BalanceJSON::from(amount_to_withdraw),
+                withdrawn,
                 &env::current_account_id(),
                 0,
                 GAS_FOR_RESOLVE_TRANSFER
@@ -54,16 +47,73 @@ impl KatherineFundraising {
         );
     }
 
+    fn get_amount_to_withdraw_before_freeze(&self, requested_amount: Balance, deposit: Balance) -> Balance {
+        assert!(requested_amount <= deposit, "Not available amount!");
+
+        // Ensure that the min_deposit_amount remains after a withdraw.
+        if is_close(requested_amount, deposit)
+                || (deposit - requested_amount) < self.min_deposit_amount {
+            deposit
+        } else {
+            requested_amount
+        }
+    }
+
+    /// Moves the stNEAR of the Supporter to another Kickstarter. Only the internal accounting
+    /// changes, no tokens are transferred. The source is released before checking the target,
+    /// so a full move only requires the storage difference.
+    pub(crate) fn internal_move_deposit(
+        &mut self,
+        requested_amount: Balance,
+        from_kickstarter: &mut Kickstarter,
+        to_kickstarter: &mut Kickstarter,
+        supporter_id: SupporterId
+    ) -> Balance {
+        let deposit = from_kickstarter.get_deposit(&supporter_id);
+        let amount_to_move = self.get_amount_to_withdraw_before_freeze(requested_amount, deposit);
+        self.supporter_withdraw_before_freeze(
+            amount_to_move,
+            deposit,
+            from_kickstarter,
+            &supporter_id
+        );
+
+        // If the target is rejected, the panic reverts the source withdraw.
+        match self.check_supporter_deposit(&supporter_id, &supporter_id, amount_to_move, &to_kickstarter) {
+            Ok(accepted) => assert!(
+                accepted == amount_to_move,
+                "The deposits hard cap of the target Kickstarter cannot be exceeded!"
+            ),
+            Err(reason) => panic!("{}", reason),
+        }
+        self.process_supporter_deposit(
+            &supporter_id,
+            &supporter_id,
+            &amount_to_move,
+            None,
+            to_kickstarter
+        );
+        log!(
+            "MOVE: {} stNEAR moved by {} from KickstarterId {} to KickstarterId {}",
+            amount_to_move,
+            supporter_id,
+            from_kickstarter.id,
+            to_kickstarter.id
+        );
+        amount_to_move
+    }
+
     /// This function is for the Supporter withdrawal of stNear tokens. The kickstarter.total_deposited
     /// is only modified during the funding period. After the project evaluation, the value is kept only
     /// as a reference. NO REWARDS ACHIEVED!
+    /// Returns the deposits removed during the funding period, to restore them if the transfer fails.
     fn supporter_withdraw_before_freeze(
         &mut self,
         requested_amount: Balance,
         deposit: Balance,
         kickstarter: &mut Kickstarter,
         supporter_id: &SupporterId
-    ) {
+    ) -> Option<WithdrawnDepositsJSON> {
         if deposit == requested_amount {
             kickstarter.deposits.remove(&supporter_id);
             // Remove Kickstarter from the supported projects.
@@ -75,10 +125,18 @@ impl KatherineFundraising {
             let new_total = deposit - requested_amount;
             kickstarter.deposits.insert(&supporter_id, &new_total);
         }
-        if kickstarter.is_within_funding_period() {
+        let withdrawn = if kickstarter.is_within_funding_period() {
+            let mut withdrawn = kickstarter.remove_weighted_deposits(&supporter_id, deposit, requested_amount);
             kickstarter.total_deposited -= requested_amount;
-        }
-        self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+            withdrawn.referred_deposit = BalanceJSON::from(
+                kickstarter.remove_referred_deposits(&supporter_id, requested_amount)
+            );
+            Some(withdrawn)
+        } else {
+            None
+        };
+        self.internal_save_kickstarter(&kickstarter);
+        withdrawn
     }
 
     #[private]
@@ -86,7 +144,8 @@ impl KatherineFundraising {
         &mut self,
         supporter_id: SupporterIdJSON,
         kickstarter_id: KickstarterIdJSON,
-        amount: BalanceJSON
+        amount: BalanceJSON,
+        withdrawn: Option<WithdrawnDepositsJSON>
     ) {
         let amount = amount.0;
         let supporter_id = supporter_id.to_string();
@@ -108,31 +167,43 @@ impl KatherineFundraising {
                 self.internal_restore_withdraw_before_freeze(
                     amount,
                     kickstarter_id,
-                    supporter_id.to_string()
+                    supporter_id.to_string(),
+                    withdrawn
                 );
             },
         };
     }
 
+    /// Mirrors the withdraw, the Kickstarter totals are only restored if the withdraw removed
+    /// them during the funding period.
     fn internal_restore_withdraw_before_freeze(
         &mut self,
         amount: Balance,
         kickstarter_id: KickstarterId,
         supporter_id: AccountId,
+        withdrawn: Option<WithdrawnDepositsJSON>,
     ) {
         let mut kickstarter = self.internal_get_kickstarter(kickstarter_id);
-        let deposit = match kickstarter.deposits.get(&supporter_id) {
+        let current_deposit = match kickstarter.deposits.get(&supporter_id) {
             None => {
                 // If the deposit was deleted, then restore the supported project too.
                 let mut supporter = self.internal_get_supporter(&supporter_id);
                 supporter.supported_projects.insert(&kickstarter.id);
                 self.supporters.insert(&supporter_id, &supporter);
-                amount
+                0
             },
-            Some(balance) => balance + amount,
-        }; 
-        kickstarter.deposits.insert(&supporter_id, &deposit);
-        self.kickstarters.replace(kickstarter_id as u64, &kickstarter);
+            Some(balance) => balance,
+        };
+        if let Some(withdrawn) = withdrawn {
+            kickstarter.restore_weighted_deposits(&supporter_id, current_deposit, amount, &withdrawn);
+            kickstarter.total_deposited += amount;
+            // The referrer of the Supporter is kept by the withdraw.
+            if withdrawn.referred_deposit.0 > 0 {
+                kickstarter.update_referred_deposits(&supporter_id, None, withdrawn.referred_deposit.0);
+            }
+        }
+        kickstarter.deposits.insert(&supporter_id, &(current_deposit + amount));
+        self.internal_save_kickstarter(&kickstarter);
     }
 
     pub(crate) fn internal_supporter_withdraw_after_unfreeze(
@@ -206,7 +277,7 @@ impl KatherineFundraising {
             );
         }
 
-        self.kickstarters.replace(kickstarter.id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
     }
 
     /// The contrapart in the claim for this function is **remove_from_supported_claim**.
@@ -215,11 +286,7 @@ impl KatherineFundraising {
         kickstarter: &mut Kickstarter,
         supporter_id: &SupporterId
     ) {
-        let rewards = self.internal_get_supporter_rewards(
-            &supporter_id,
-            &kickstarter,
-            kickstarter.get_winner_goal().tokens_to_release_per_stnear
-        );
+        let rewards = self.internal_get_supporter_rewards(&supporter_id, &kickstarter);
         if rewards == 0 {
             let mut supporter = self.internal_get_supporter(&supporter_id);
             supporter.supported_projects.remove(&kickstarter.id);
@@ -273,7 +340,7 @@ impl KatherineFundraising {
         let new_withdraw = supporter_withdraw - amount;
 
         kickstarter.stnear_withdraw.insert(&entity, &new_withdraw);
-        self.kickstarters.replace(kickstarter_id as u64, &kickstarter);
+        self.internal_save_kickstarter(&kickstarter);
 
         // If the withdraw fails, add the Kickstarter back to the supported projects.
         if supporter_withdraw == deposit {
@@ -283,3 +350,207 @@ impl KatherineFundraising {
         }
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::json_types::U128;
+    use near_sdk::{PromiseResult, VMContext};
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::tests::unit_test_utils::*;
+
+    /// The Kickstarter totals that a withdraw updates.
+    #[derive(Debug, PartialEq)]
+    struct Aggregates {
+        deposit: Option<Balance>,
+        is_supporting: bool,
+        total_deposited: Balance,
+        tier_deposits: Option<Vec<Balance>>,
+        total_weighted_deposits: Balance,
+        deposit_amounts: Vec<(Balance, u32)>,
+        time_weighted_deposit: Balance,
+        total_time_weighted_deposits: Balance,
+        supporter_referred_deposit: Balance,
+        referred_deposits: Balance,
+        total_referred: Balance,
+    }
+
+    fn get_aggregates(
+        contract: &KatherineFundraising,
+        kickstarter_id: KickstarterId,
+        supporter_id: &SupporterId
+    ) -> Aggregates {
+        let kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        Aggregates {
+            deposit: kickstarter.deposits.get(&supporter_id),
+            is_supporting: contract.internal_get_supporter(&supporter_id).is_supporting(kickstarter_id),
+            total_deposited: kickstarter.total_deposited,
+            tier_deposits: kickstarter.tier_deposits.get(&supporter_id),
+            total_weighted_deposits: kickstarter.total_weighted_deposits,
+            deposit_amounts: kickstarter.deposit_amounts.iter().collect(),
+            time_weighted_deposit: kickstarter.get_time_weighted_deposit(&supporter_id),
+            total_time_weighted_deposits: kickstarter.total_time_weighted_deposits,
+            supporter_referred_deposit: kickstarter.get_supporter_referred_deposit(&supporter_id),
+            referred_deposits: kickstarter.get_referred_deposits(&REFERRER_ACCOUNT.to_string()),
+            total_referred: kickstarter.total_referred,
+        }
+    }
+
+    /// A funded time-weighted Kickstarter with two early-bird tiers, of one day each from the
+    /// open. The Supporter was referred before the first deposit.
+    fn setup_funded_kickstarter(context: &mut VMContext, contract: &mut KatherineFundraising) -> KickstarterId {
+        let kickstarter_id = _new_kickstarter(context.clone(), contract);
+        let mut kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        kickstarter.time_weighted = true;
+        kickstarter.referral_fee_percent = 500;
+        kickstarter.deposit_tiers = vec![
+            DepositTier { end_timestamp: kickstarter.open_timestamp + DAY_MILLIS, multiplier: 15_000 },
+            DepositTier { end_timestamp: kickstarter.open_timestamp + 2 * DAY_MILLIS, multiplier: 12_000 },
+        ];
+        kickstarter.referrers.insert(&SUPPORTER_ACCOUNT.to_string(), &REFERRER_ACCOUNT.to_string());
+        contract.internal_save_kickstarter(&kickstarter);
+        _new_goal(contract, kickstarter_id, ntoy(100), ntoy(1));
+        _fund_kickstarter(context, contract, kickstarter_id);
+        kickstarter_id
+    }
+
+    fn withdraw_and_fail(
+        context: &mut VMContext,
+        contract: &mut KatherineFundraising,
+        kickstarter_id: KickstarterId,
+        supporter_id: &SupporterId,
+        amount: Balance,
+    ) {
+        let mut kickstarter = contract.internal_get_kickstarter(kickstarter_id);
+        let deposit = kickstarter.get_deposit(&supporter_id);
+        let withdrawn = contract.supporter_withdraw_before_freeze(
+            amount,
+            deposit,
+            &mut kickstarter,
+            &supporter_id
+        );
+        set_predecessor(context, CONTRACT_ACCOUNT);
+        set_promise_result(context, PromiseResult::Failed);
+        contract.return_tokens_before_freeze_callback(
+            supporter_id.clone().try_into().unwrap(),
+            kickstarter_id,
+            BalanceJSON::from(amount),
+            withdrawn
+        );
+    }
+
+    #[test]
+    fn test_failed_withdraw_restores_the_aggregates() {
+        let (mut context, mut contract) = contract_only_setup();
+        let kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(10));
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, OTHER_SUPPORTER_ACCOUNT, ntoy(10));
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS) + DAY_MILLIS);
+        _supporter_deposit(&mut context, &mut contract, kickstarter_id, SUPPORTER_ACCOUNT, ntoy(10));
+        let before = get_aggregates(&contract, kickstarter_id, &supporter_id);
+        assert_eq!(before.tier_deposits, Some(vec![ntoy(10), ntoy(10), 0]));
+        assert_eq!(before.deposit_amounts, vec![(ntoy(10), 1), (ntoy(20), 1)]);
+        assert!(before.time_weighted_deposit > 0);
+        assert_eq!(before.referred_deposits, ntoy(20));
+
+        // The restore is later in the funding period, the weight must not be taken again.
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS) + 2 * DAY_MILLIS);
+
+        // A partial withdraw.
+        withdraw_and_fail(&mut context, &mut contract, kickstarter_id, &supporter_id, ntoy(15));
+        assert_eq!(get_aggregates(&contract, kickstarter_id, &supporter_id), before);
+
+        // A full withdraw, the Kickstarter is removed from the supported projects.
+        withdraw_and_fail(&mut context, &mut contract, kickstarter_id, &supporter_id, ntoy(20));
+        assert_eq!(get_aggregates(&contract, kickstarter_id, &supporter_id), before);
+    }
+
+    #[test]
+    fn test_move_full_deposit() {
+        let (mut context, mut contract) = contract_only_setup();
+        let from_kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        let to_kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, from_kickstarter_id, SUPPORTER_ACCOUNT, ntoy(10));
+        _supporter_deposit(&mut context, &mut contract, to_kickstarter_id, OTHER_SUPPORTER_ACCOUNT, ntoy(10));
+        let storage_before = contract.storage_balance_of(SUPPORTER_ACCOUNT.try_into().unwrap()).unwrap();
+        assert_eq!(storage_before.available.0, 0);
+
+        // The storage of the source covers the target.
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        let moved = contract.move_deposit(from_kickstarter_id, to_kickstarter_id, U128::from(ntoy(10)));
+        assert_eq!(moved.0, ntoy(10));
+
+        let from_aggregates = get_aggregates(&contract, from_kickstarter_id, &supporter_id);
+        assert_eq!(from_aggregates.deposit, None);
+        assert!(!from_aggregates.is_supporting);
+        assert_eq!(from_aggregates.total_deposited, 0);
+        assert_eq!(from_aggregates.total_weighted_deposits, 0);
+        assert_eq!(from_aggregates.total_referred, 0);
+        let to_aggregates = get_aggregates(&contract, to_kickstarter_id, &supporter_id);
+        assert_eq!(to_aggregates.deposit, Some(ntoy(10)));
+        assert!(to_aggregates.is_supporting);
+        assert_eq!(to_aggregates.total_deposited, ntoy(20));
+        assert_eq!(to_aggregates.total_weighted_deposits, ntoy(30));
+        assert_eq!(to_aggregates.referred_deposits, ntoy(10));
+        let storage_after = contract.storage_balance_of(SUPPORTER_ACCOUNT.try_into().unwrap()).unwrap();
+        assert_eq!(storage_after.total, storage_before.total);
+        assert_eq!(storage_after.available.0, 0);
+    }
+
+    #[test]
+    fn test_move_partial_deposit() {
+        let (mut context, mut contract) = contract_only_setup();
+        let from_kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        let to_kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        let supporter_id = SUPPORTER_ACCOUNT.to_string();
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, from_kickstarter_id, SUPPORTER_ACCOUNT, ntoy(10));
+
+        // Both Kickstarters are supported after a partial move, only the new one is charged.
+        let to_kickstarter = contract.internal_get_kickstarter(to_kickstarter_id);
+        let missing_storage = contract.internal_missing_storage(&supporter_id, &to_kickstarter);
+        assert_eq!(missing_storage, contract.storage_cost(2) - contract.storage_cost(1));
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        set_attached_deposit(&mut context, missing_storage);
+        contract.storage_deposit(None, None);
+        set_attached_deposit(&mut context, 0);
+        let moved = contract.move_deposit(from_kickstarter_id, to_kickstarter_id, U128::from(ntoy(4)));
+        assert_eq!(moved.0, ntoy(4));
+
+        let from_aggregates = get_aggregates(&contract, from_kickstarter_id, &supporter_id);
+        assert_eq!(from_aggregates.deposit, Some(ntoy(6)));
+        assert!(from_aggregates.is_supporting);
+        assert_eq!(from_aggregates.total_deposited, ntoy(6));
+        assert_eq!(from_aggregates.tier_deposits, Some(vec![ntoy(6), 0, 0]));
+        assert_eq!(from_aggregates.total_weighted_deposits, ntoy(9));
+        assert_eq!(from_aggregates.deposit_amounts, vec![(ntoy(6), 1)]);
+        assert_eq!(from_aggregates.referred_deposits, ntoy(6));
+        let to_aggregates = get_aggregates(&contract, to_kickstarter_id, &supporter_id);
+        assert_eq!(to_aggregates.deposit, Some(ntoy(4)));
+        assert!(to_aggregates.is_supporting);
+        assert_eq!(to_aggregates.total_deposited, ntoy(4));
+        assert_eq!(to_aggregates.total_weighted_deposits, ntoy(6));
+        assert_eq!(to_aggregates.deposit_amounts, vec![(ntoy(4), 1)]);
+        assert_eq!(to_aggregates.referred_deposits, ntoy(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "must cover the storage with storage_deposit")]
+    fn test_move_partial_deposit_without_storage() {
+        let (mut context, mut contract) = contract_only_setup();
+        let from_kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        let to_kickstarter_id = setup_funded_kickstarter(&mut context, &mut contract);
+        set_timestamp(&mut context, to_millis(OPEN_TIME_IN_DAYS));
+        _supporter_deposit(&mut context, &mut contract, from_kickstarter_id, SUPPORTER_ACCOUNT, ntoy(10));
+
+        set_predecessor(&mut context, SUPPORTER_ACCOUNT);
+        contract.move_deposit(from_kickstarter_id, to_kickstarter_id, U128::from(ntoy(4)));
+    }
+}
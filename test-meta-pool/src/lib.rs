@@ -25,7 +25,7 @@ use near_sdk::json_types::U128;
 use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseOrValue};
 
 mod types;
-use crate::types::GetAccountInfoResult;
+use crate::types::{GetAccountInfoResult, U256};
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
@@ -34,6 +34,8 @@ pub struct Contract {
     metadata: LazyOption<FungibleTokenMetadata>,
 }
 
+const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
 
 #[near_bindgen]
@@ -101,6 +103,22 @@ impl Contract {
         U128::from(result.parse::<u128>().unwrap())
     }
 
+    /// Mints stNEAR for the attached NEAR at the current dummy price.
+    #[payable]
+    pub fn deposit_and_stake(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "Attach the NEAR to stake");
+        let st_near_amount =
+            (U256::from(amount) * U256::from(ONE_NEAR) / U256::from(self.get_st_near_price().0)).as_u128();
+        if !self.token.accounts.contains_key(&account_id) {
+            self.token.internal_register_account(&account_id);
+        }
+        self.token.internal_deposit(&account_id, st_near_amount);
+        log!("Account @{} staked {} for {} stNEAR", account_id, amount, st_near_amount);
+        U128::from(st_near_amount)
+    }
+
     pub fn get_account_info(&self, account_id: AccountId) -> GetAccountInfoResult {
         return GetAccountInfoResult {
             account_id: account_id.clone(),
@@ -203,4 +221,21 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - transfer_amount));
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
     }
+
+    #[test]
+    fn test_deposit_and_stake() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
+        // The dummy price is the timestamp padded to 25 digits, 2 NEAR per stNEAR.
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(2_000_000_000_000_000_000)
+            .attached_deposit(10 * ONE_NEAR)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let st_near_amount = contract.deposit_and_stake();
+        assert_eq!(st_near_amount.0, 5 * ONE_NEAR);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 5 * ONE_NEAR);
+    }
 }
\ No newline at end of file